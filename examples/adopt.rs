@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use muted::{Muted as Generic, MutedIn};
+
+//buffers that already exist get adopted as fragments without copying or wrapping a single element
+fn main() {
+    let mut muted: Generic<u64> = Generic::new(vec![1, 2, 3]);
+
+    //a boxed slice is taken over as it is, same allocation afterwards
+    let boxed: Box<[u64]> = (4..8).collect();
+    let addr = boxed.as_ptr();
+    let owned = muted.push_boxed(boxed);
+    assert_eq!(muted.fragment(owned).unwrap().as_ptr(), addr);
+    muted.write(3, 40).unwrap();
+
+    //shared with whoever else holds the Arc, so reads only
+    let shared: Arc<[u64]> = Arc::from(vec![8, 9]);
+    let id = muted.push_shared(Arc::clone(&shared));
+    assert_eq!(muted.fragment(id), Some(&[8, 9][..]));
+    assert!(muted.fragment_mut(id).is_none());
+    assert_eq!(muted.write(7, 80), None);
+    assert_eq!(muted.take_fragment(id), None);
+    assert_eq!(Arc::strong_count(&shared), 2);
+
+    println!("{}", muted); // [1, 2, 3, 40, 5, 6, 7, 8, 9]
+
+    //owned fragments get merged, the shared one stays where it is
+    muted.compact();
+    println!("{} {:?}", muted, muted.iter().collect::<Vec<_>>());
+    drop(muted);
+    assert_eq!(Arc::strong_count(&shared), 1);
+
+    //borrowing ties the container to the slice
+    let on_stack = [10u64, 11, 12];
+    let mut borrowed: MutedIn<'_, u64> = MutedIn::new(vec![0]);
+    let id = borrowed.push_slice(&on_stack);
+    assert!(borrowed.fragment_mut(id).is_none());
+    assert_eq!(borrowed.fragment(id).unwrap().as_ptr(), on_stack.as_ptr());
+    assert_eq!(borrowed.read(2), Some(&11));
+    assert_eq!(borrowed.write(2, 0), None);
+    println!("{}", borrowed.iter().sum::<u64>());
+}
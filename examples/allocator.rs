@@ -0,0 +1,48 @@
+//cargo +nightly run --example allocator --features nightly
+#![feature(allocator_api)]
+use std::alloc::{AllocError, Allocator, Global, Layout};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use muted::{Muted as Generic, RawBuf, UnionMuted};
+
+//hands everything to Global but keeps track of how many bytes are live, stand in for an arena
+#[derive(Clone, Default)]
+struct Counting(Arc<AtomicUsize>);
+
+unsafe impl Allocator for Counting {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.0.fetch_add(layout.size(), Ordering::Relaxed);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.0.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { Global.deallocate(ptr, layout) }
+    }
+}
+
+fn main() {
+    let alloc = Counting::default();
+    {
+        let mut muted: Generic<u64, muted::FenwickIndex, Counting> = Generic::new_in(vec![1, 2, 3], alloc.clone());
+        let id = muted.push_vec(vec![4, 5, 6]);
+        muted.push_vec(vec![7]);
+        println!("generic: {} bytes live for {}", alloc.0.load(Ordering::Relaxed), muted);
+        muted.drop_fragment(id).unwrap();
+        let copy = muted.clone();
+        assert_eq!(copy.iter().collect::<Vec<_>>(), muted.iter().collect::<Vec<_>>());
+    }
+    assert_eq!(alloc.0.load(Ordering::Relaxed), 0);
+
+    {
+        let mut union: UnionMuted<String, Counting> = UnionMuted::new_in(vec![String::from("a")], alloc.clone());
+        union.push_vec_convert(vec![String::from("b"), String::from("c")]);
+        union.compact();
+        println!("union: {} bytes live for {}", alloc.0.load(Ordering::Relaxed), union);
+    }
+    assert_eq!(alloc.0.load(Ordering::Relaxed), 0);
+
+    let buf = RawBuf::new_in(16, alloc.clone());
+    println!("raw buf: {} bytes live for {} bits", alloc.0.load(Ordering::Relaxed), buf.len);
+}
@@ -0,0 +1,47 @@
+use std::time::Instant;
+use muted::Muted as Generic;
+
+fn fill(muted: &mut Generic<u32>, count: u32) -> Vec<muted::FragmentId> {
+    (0..count).map(|f| muted.push_vec(vec![f, f + 1])).collect()
+}
+
+//lots of tiny fragments, arena mode keeps their headers in one slab instead of a box and a hash map entry each
+fn main() {
+    let count = 1_000_000;
+
+    let mut boxed: Generic<u32> = Generic::new(vec![]);
+    let start = Instant::now();
+    fill(&mut boxed, count);
+    println!("boxed: {} fragments in {:?}", count, start.elapsed());
+
+    let mut arena: Generic<u32> = Generic::new(vec![]);
+    arena.set_arena(true);
+    let start = Instant::now();
+    let ids = fill(&mut arena, count);
+    println!("arena: {} fragments in {:?}", count, start.elapsed());
+
+    assert_eq!(arena.len(), boxed.len());
+    assert_eq!(arena.read(2 * 500_000 + 1), Some(&500_001));
+    assert_eq!(arena.fragment_range(ids[10]), Some(20..22));
+
+    //ids, holes and writes work the same as without the arena
+    arena.drop_fragment(ids[10]).unwrap();
+    assert!(arena.fragment(ids[10]).is_none());
+    assert_eq!(arena.insert_vec(10, vec![7, 8, 9]).map(|id| id.slot()), Some(10));
+    arena.write(20, 70).unwrap();
+    assert_eq!(arena.read(20), Some(&70));
+    assert_eq!(arena.take_fragment(ids[11]), Some(vec![11, 12]));
+
+    //a clone copies the fragments since there is no Arc to share
+    let copy = arena.clone();
+    arena.write(0, 100).unwrap();
+    assert_eq!(copy.read(0), Some(&0));
+
+    //switching back moves every fragment into its own box again
+    arena.set_arena(false);
+    assert_eq!(arena.read(0), Some(&100));
+    assert_eq!(arena.len(), copy.len());
+    arena.set_arena(true);
+    arena.compact();
+    println!("{} {}", arena.len(), arena.iter().zip(copy.iter()).filter(|(a, b)| a != b).count());
+}
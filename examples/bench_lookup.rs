@@ -0,0 +1,62 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+//compares the plain prefix index lookup against the cursor cache
+//run with --release, debug numbers are meaningless
+const FRAGMENTS: usize = 10_000;
+const FRAGMENT_LEN: usize = 64;
+const ROUNDS: usize = 5;
+
+fn build(cached: bool) -> muted::generic::Muted<u64> {
+    let mut muted = muted::generic::Muted::new(vec![]);
+    for f in 0..FRAGMENTS {
+        let start = (f * FRAGMENT_LEN) as u64;
+        muted.push_vec_convert((start..start + FRAGMENT_LEN as u64).collect());
+    }
+    muted.set_cursor_cache(cached);
+    muted
+}
+
+fn time(name: &str, muted: &muted::generic::Muted<u64>, indices: &[usize]) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        let mut sum = 0u64;
+        for &i in indices {
+            sum = sum.wrapping_add(*muted.read(i).unwrap());
+        }
+        black_box(sum);
+        best = best.min(start.elapsed());
+    }
+    println!("{:<28} {:>10.2?} ({:.2} ns/read)", name, best, best.as_nanos() as f64 / indices.len() as f64);
+    best
+}
+
+fn main() {
+    let len = FRAGMENTS * FRAGMENT_LEN;
+    let sequential: Vec<usize> = (0..len).collect();
+    //small forward jumps, mostly landing in the same or the next fragment
+    let mut near: Vec<usize> = Vec::with_capacity(len);
+    let mut i = 0;
+    while i < len {
+        near.push(i);
+        i += 1 + (i * 7919) % 13;
+    }
+    //xorshift so the numbers stay the same between runs
+    let mut seed = 0x2545F4914F6CDD1Du64;
+    let random: Vec<usize> = (0..len).map(|_| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % len as u64) as usize
+    }).collect();
+
+    let plain = build(false);
+    let cached = build(true);
+    for (label, indices) in [("sequential", &sequential), ("near sequential", &near), ("random", &random)] {
+        println!("{} ({} reads)", label, indices.len());
+        let a = time("  prefix index", &plain, indices);
+        let b = time("  prefix index + cursor", &cached, indices);
+        println!("  speedup {:.2}x", a.as_secs_f64() / b.as_secs_f64());
+    }
+}
@@ -0,0 +1,50 @@
+use std::io::Cursor;
+use std::time::Instant;
+use muted::PrimitiveMuted;
+
+//dump and reload through the native binary format, run with --release for meaningful numbers
+fn main() {
+    let mut muted = PrimitiveMuted::new((0..1000u64).collect(), false);
+    for f in 1..1000u64 {
+        muted.push_vec((f * 10_000..(f + 1) * 10_000).collect());
+    }
+    let hole = muted.push_vec(vec![1, 2, 3]);
+    muted.drop_vec(hole).unwrap();
+    muted.push_vec(vec![42]);
+
+    let start = Instant::now();
+    let mut bytes = Vec::new();
+    muted.write_to(&mut bytes).unwrap();
+    let written = start.elapsed();
+    let start = Instant::now();
+    let back: PrimitiveMuted<u64> = PrimitiveMuted::read_from(Cursor::new(&bytes)).unwrap();
+    println!("{} elements, {} bytes, write {:?}, read {:?}", muted.len(), bytes.len(), written, start.elapsed());
+    assert!(back.iter().eq(muted.iter()));
+
+    //same layout comes back, so writing it again gives the same bytes
+    let mut again = Vec::new();
+    back.write_to(&mut again).unwrap();
+    assert_eq!(again, bytes);
+
+    //a flipped bit anywhere is caught by the checksum
+    let mut corrupt = bytes.clone();
+    let last = corrupt.len() - 100;
+    corrupt[last] ^= 1;
+    let err = PrimitiveMuted::<u64>::read_from(Cursor::new(&corrupt)).err().unwrap();
+    println!("{}", err); // checksum mismatch
+    //and the element size is part of the header
+    let err = PrimitiveMuted::<u32>::read_from(Cursor::new(&bytes)).err().unwrap();
+    println!("{}", err); // element size doesn't match T
+
+    let mut generic = muted::generic::Muted::new(vec![1i32, 2, 3]);
+    let id = generic.push_vec_convert(vec![4, 5]);
+    generic.push_vec_convert(vec![6]);
+    generic.drop_fragment(id).unwrap();
+    let mut bytes = Vec::new();
+    generic.write_to(&mut bytes).unwrap();
+    let mut back: muted::generic::Muted<i32> = muted::generic::Muted::read_from(bytes.as_slice()).unwrap();
+    println!("{}", back); // [1, 2, 3, 6]
+    //the hole is still there to be refilled
+    back.insert_vec(3, vec![4, 5]).unwrap();
+    println!("{}", back); // [1, 2, 3, 4, 5, 6]
+}
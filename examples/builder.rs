@@ -0,0 +1,42 @@
+use muted::{CompactPolicy, FragmentedVec, LinearIndex, MutedBuilder, PrimitiveWidth};
+
+fn churn<C: FragmentedVec<u32>>(muted: &mut C) {
+    for f in 0..10u32 {
+        let id = muted.push_vec(vec![f; 8]);
+        if f % 2 == 0 {
+            muted.drop_fragment(id).unwrap();
+        }
+    }
+}
+
+//the same settings for all three containers, each one takes what applies to it
+fn main() {
+    let builder = || MutedBuilder::<u32>::new()
+        .slot_capacity(64)
+        .fragment_capacity(16)
+        .prefix_capacity(64)
+        .compact_policy(CompactPolicy::AfterDrops(5))
+        .inline_threshold(4);
+
+    let mut generic = builder().build(vec![1, 2, 3]);
+    churn(&mut generic);
+    //the fifth drop compacted everything so far into slot 0, the last push came after that
+    assert_eq!(generic.len(), 3 + 5 * 8);
+    assert_eq!(generic.take_fragment(0).map(|v| v.len()), Some(35));
+    assert_eq!(generic.take_fragment(1), Some(vec![9; 8]));
+
+    let mut linear = builder().build_with_index::<LinearIndex>(vec![1, 2, 3]);
+    churn(&mut linear);
+    assert_eq!(linear.len(), 43);
+
+    let mut primitive = builder().width(PrimitiveWidth::Bits32).build_primitive(vec![1, 2, 3]);
+    churn(&mut primitive);
+    assert_eq!(primitive.len(), 43);
+
+    let mut union = builder().build_union(vec![1, 2, 3]);
+    churn(&mut union);
+    assert_eq!(union.len(), 43);
+
+    let wide = MutedBuilder::<u64>::new().build_primitive(vec![u64::MAX]);
+    println!("{} {} {} {}", generic.len(), primitive.len(), union.len(), wide.read(0).unwrap());
+}
@@ -0,0 +1,47 @@
+use std::thread;
+use muted::ConcurrentMuted;
+
+//producers push fragments from their own threads while a reader keeps looking at published snapshots
+fn main() {
+    let muted: ConcurrentMuted<u64> = ConcurrentMuted::with_shards(4);
+    const PRODUCERS: u64 = 8;
+    const FRAGMENTS: u64 = 200;
+
+    thread::scope(|s| {
+        for p in 0..PRODUCERS {
+            let muted = &muted;
+            s.spawn(move || {
+                for f in 0..FRAGMENTS {
+                    let start = (p * FRAGMENTS + f) * 4;
+                    muted.push_vec((start..start + 4).collect());
+                    if f % 50 == 0 {
+                        muted.publish();
+                    }
+                }
+            });
+        }
+        s.spawn(|| {
+            let mut last = 0;
+            for _ in 0..100 {
+                let snapshot = muted.snapshot();
+                //a snapshot never shrinks and every element in it is readable
+                assert!(snapshot.len() >= last);
+                last = snapshot.len();
+                for i in 0..snapshot.len() {
+                    snapshot.read(i).unwrap();
+                }
+                thread::yield_now();
+            }
+        });
+    });
+
+    let snapshot = muted.publish();
+    let mut all: Vec<u64> = snapshot.iter().copied().collect();
+    assert_eq!(all.len() as u64, PRODUCERS * FRAGMENTS * 4);
+    all.sort();
+    assert!(all.iter().enumerate().all(|(i, v)| i as u64 == *v));
+    //snapshots can be moved into other threads on their own
+    let moved = snapshot.clone();
+    let first = thread::spawn(move || *moved.read(0).unwrap()).join().unwrap();
+    println!("{} elements in {} reads, first {}", snapshot.len(), all.len(), first);
+}
@@ -0,0 +1,38 @@
+use std::time::Instant;
+use muted::Muted as Generic;
+
+//clones share every fragment, a write copies only the fragment it lands in
+fn main() {
+    let mut muted: Generic<u64> = Generic::new(vec![0]);
+    let mut ids = Vec::new();
+    for f in 0..1000u64 {
+        ids.push(muted.push_vec((f * 10_000..(f + 1) * 10_000).collect()));
+    }
+
+    let start = Instant::now();
+    let mut copy = muted.clone();
+    println!("clone of {} elements took {:?}", muted.len(), start.elapsed());
+
+    let first = |m: &Generic<u64>, i: usize| m.fragment(ids[i]).unwrap().as_ptr();
+    assert_eq!(first(&muted, 3), first(&copy, 3));
+
+    //index 1 + 3 * 10_000 is the start of fragment 3
+    copy.write(30_001, 7).unwrap();
+    assert_eq!(copy.read(30_001), Some(&7));
+    assert_eq!(muted.read(30_001), Some(&30_000));
+    assert_ne!(first(&muted, 3), first(&copy, 3));
+    assert_eq!(first(&muted, 4), first(&copy, 4));
+
+    //the original was left alone by the copy, its own write goes through without copying again
+    let before = first(&muted, 3);
+    muted.write(30_001, 9).unwrap();
+    assert_eq!(first(&muted, 3), before);
+
+    //taking a shared fragment out copies it, the clone keeps its own
+    let taken = muted.take_fragment(ids[4]).unwrap();
+    assert_eq!(taken[0], 40_000);
+    assert_eq!(copy.read(40_001), Some(&40_000));
+
+    copy.compact();
+    println!("{} {} {}", muted.len(), copy.len(), copy.iter().filter(|v| **v == 7).count());
+}
@@ -13,18 +13,14 @@ struct ComplexStructure{
 fn main() {
     //generic and primitive behave similarly except the primitive one is around 3 times faster with primitive Eq types so u32/i32 and u64/i64
     //therefore does not allow complex types like structs... for most things just use the generic one or just look in the benchmark branch for more details
-    let mut super_dangerous_reference: *mut Muted::generic::Muted<i32>;
+    let mut super_dangerous_reference: *mut muted::generic::Muted<i32>;
 
     {
-        let mut muted = Muted::generic::Muted::new(vec![1, 2, 3, 4, 5]);
+        let mut muted = muted::generic::Muted::new(vec![1, 2, 3, 4, 5]);
         super_dangerous_reference = &mut muted;
+        muted.push_vec(vec![1, 2, 3, 4, 5]);
+        //fragments keep the vec as it is so this is O(1), push_vec_convert is the same thing under its old name
         muted.push_vec_convert(vec![1, 2, 3, 4, 5]);
-        //use convert method if not already in the right data type
-        let converted = Muted::generic::Muted::muted_from(vec![1, 2, 3, 4, 5]);
-        muted.push_vec(converted);
-        /* this now works and is a lot faster so if you have spare time just convert it first
-        the non converted method is O(1) compared to O(n)
-        */
         println!("{}", muted); // [1, 2, 3, 4, 5, 1, 2, 3, 4, 5, 1, 2, 3, 4, 5]
         //indexing is quite easy really...
         let _ = muted.read(5); // reads index 5, this is immutable
@@ -59,7 +55,7 @@ fn main() {
     //but its actually freed, the pointers are explicitly removed and its not a leak and the OS can reclaim the memory whenever it wants
 
     //also about non-primitive types... well no problem really
-    let mut muted_complex = Muted::generic::Muted::new(vec![
+    let mut muted_complex = muted::generic::Muted::new(vec![
         ComplexStructure{
             part1: 1,
             part2: -1,
@@ -75,5 +71,13 @@ fn main() {
     println!("{}", muted_complex); // [ComplexStructure { part1: 1, part2: -1, part3: That },
     // ComplexStructure { part1: 100, part2: -500, part3: This }]
     println!("{:?}", muted_complex.read(1).unwrap().part2); // -500
+
+    //the primitive one has the same lifecycle, drop_vec takes either the slot or the handle from push_vec
+    let mut muted_primitive = muted::primitive::PrimitiveMuted::new(vec![1u64, 2, 3], false);
+    let id = muted_primitive.push_vec(vec![4, 5]);
+    muted_primitive.drop_vec(id).unwrap();
+    println!("{} {}", muted_primitive, muted_primitive.len()); // [1, 2, 3] 3
+    muted_primitive.insert_vec(3, vec![6, 7, 8]).unwrap();
+    println!("{}", muted_primitive); // [1, 2, 3, 6, 7, 8]
     
 }
\ No newline at end of file
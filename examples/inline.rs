@@ -0,0 +1,22 @@
+use muted::{Muted as Generic, PrimitiveMuted};
+
+//vecs below the threshold become plain inline slots, longer ones are still adopted as fragments
+fn main() {
+    let mut muted: Generic<u32> = Generic::new(vec![0]).with_inline_threshold(4);
+    let small = muted.push_vec(vec![1, 2, 3]);
+    let big = muted.push_vec((4..100).collect());
+    assert!(muted.fragment(small).is_none());
+    assert_eq!(muted.fragment_range(big), Some(4..100));
+    muted.write(2, 20).unwrap();
+    assert_eq!(muted.iter().take(4).copied().collect::<Vec<_>>(), vec![0, 1, 20, 3]);
+
+    let mut primitive = PrimitiveMuted::new(vec![0u64], false).with_inline_threshold(4);
+    let small = primitive.push_vec(vec![1, 2, 3]);
+    let big = primitive.push_vec((4..100).collect());
+    assert!(primitive.fragment(small).is_none());
+    assert_eq!(primitive.fragment_range(big), Some(4..100));
+    primitive.write(2, 20).unwrap();
+    assert_eq!(primitive.read(2), Some(&20));
+
+    println!("{} {} {}", muted.inline_threshold(), muted.len(), primitive.len());
+}
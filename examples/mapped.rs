@@ -0,0 +1,29 @@
+//cargo run --example mapped --features memmap2
+use std::fs::File;
+use muted::{MappedMuted, PrimitiveMuted};
+
+fn main() {
+    let path = std::env::temp_dir().join("muted_mapped_example.bin");
+    let mut muted = PrimitiveMuted::new(vec![1u64, 2, 3], false);
+    let dropped = muted.push_vec(vec![100, 200]);
+    for f in 1..=100u64 {
+        muted.push_vec((f * 1000..f * 1000 + 500).collect());
+    }
+    muted.drop_vec(dropped).unwrap();
+    muted.write_to(File::create(&path).unwrap()).unwrap();
+
+    //nothing but the header and the slot table is touched until something is read
+    let mapped: MappedMuted<u64> = unsafe { MappedMuted::open(&path) }.unwrap();
+    mapped.verify().unwrap();
+    assert_eq!(mapped.len(), muted.len());
+    assert!(mapped.iter().eq(muted.iter()));
+    assert_eq!(mapped.read(3), Some(&1000));
+    assert_eq!(mapped.read(mapped.len()), None);
+    //same chunks as the in memory one, the dropped fragment is a hole in both
+    assert!(mapped.chunks().eq(muted.chunks()));
+    println!("{} elements in {} slots, {} chunks", mapped.len(), mapped.slots(), mapped.chunks().count());
+
+    //wrong element type is refused up front
+    assert!(unsafe { MappedMuted::<u32>::open(&path) }.is_err());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,33 @@
+//cargo run --example parallel --features rayon
+use rayon::prelude::*;
+use muted::{FragmentedVec, PrimitiveMuted, UnionMuted};
+
+fn main() {
+    let mut muted = muted::generic::Muted::new((0..1000u64).collect());
+    let mut primitive = PrimitiveMuted::new((0..1000u64).collect(), false);
+    let mut union = UnionMuted::new((0..1000u64).collect());
+    for f in 1..100u64 {
+        let fragment: Vec<u64> = (f * 1000..(f + 1) * 1000).collect();
+        muted.push_vec_convert(fragment.clone());
+        primitive.push_vec(fragment.clone());
+        FragmentedVec::push_vec(&mut union, fragment);
+    }
+    let expected: u64 = (0..100_000u64).sum();
+
+    assert_eq!(muted.par_iter().sum::<u64>(), expected);
+    assert_eq!(primitive.par_iter().sum::<u64>(), expected);
+    assert_eq!(union.par_iter().sum::<u64>(), expected);
+
+    //each chunk is a whole fragment (or a run of inline values) so per chunk work sees contiguous slices
+    println!("chunks: {} {} {}", muted.par_chunks().count(), primitive.par_chunks().count(), union.par_chunks().count());
+
+    muted.par_iter_mut().for_each(|v| *v *= 2);
+    primitive.par_iter_mut().for_each(|v| *v *= 2);
+    union.par_iter_mut().for_each(|v| *v *= 2);
+    assert_eq!(muted.par_iter().sum::<u64>(), expected * 2);
+    assert_eq!(primitive.par_iter().sum::<u64>(), expected * 2);
+    assert_eq!(union.par_iter().sum::<u64>(), expected * 2);
+    assert_eq!(muted.read(5), Some(&10));
+    assert_eq!(primitive.read(99_999), Some(&199_998));
+    println!("{}", expected * 2);
+}
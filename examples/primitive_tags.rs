@@ -0,0 +1,35 @@
+use muted::{FragmentedVec, PrimitiveMuted, PrimitiveWidth};
+
+//checks that PrimitiveMuted never mistakes an inline value for a fragment pointer
+fn main() {
+    let mut muted = PrimitiveMuted::new(vec![1u64, 2, 3], false);
+    let fragment = vec![10u64, 20, 30];
+    let id = muted.push_vec(fragment);
+
+    //inline values that look exactly like a live fragment pointer
+    let addr = muted.fragment(id).unwrap().as_ptr() as u64;
+    muted.write(0, addr).unwrap();
+    muted.write(1, 0).unwrap();
+    assert_eq!(muted.read(0), Some(&addr));
+    assert_eq!(muted.read(1), Some(&0));
+    assert_eq!(muted.read(4), Some(&20));
+
+    //a zero inline value is not a hole, only dropped fragments are
+    assert_eq!(muted.insert_fragment(1, vec![7]), None);
+    muted.drop_fragment(id).unwrap();
+    assert_eq!(muted.len(), 3);
+    assert_eq!(muted.take_fragment(0usize), None);
+    let refill = muted.insert_fragment(3, vec![5, 6]).unwrap();
+    assert_eq!(muted.iter().copied().collect::<Vec<_>>(), vec![addr, 0, 3, 5, 6]);
+    assert_eq!(muted.take_fragment(refill), Some(vec![5, 6]));
+
+    //32 bit values are stored as they are, no widening through the union
+    let mut small = PrimitiveMuted::new(vec![-1i32, i32::MIN, i32::MAX], true);
+    assert_eq!(small.width(), PrimitiveWidth::Bits32);
+    small.push_vec(vec![4, 5]);
+    assert_eq!(small.iter().copied().collect::<Vec<_>>(), vec![-1, i32::MIN, i32::MAX, 4, 5]);
+
+    let empty: PrimitiveMuted<u64> = PrimitiveMuted::new(vec![], false);
+    assert_eq!(empty.len(), 0);
+    println!("{} {}", muted, small);
+}
@@ -0,0 +1,32 @@
+use muted::Muted as Generic;
+
+//single elements go onto the tail fragment instead of becoming a fragment each
+fn main() {
+    let mut muted: Generic<u32> = Generic::new(vec![0, 1]);
+    //the tail is an inline value so the first push starts a fragment, the rest land in it
+    for v in 2..1000 {
+        muted.push(v);
+    }
+    assert_eq!(muted.fragment_count(), 1);
+    assert_eq!(muted.stats().slots, 3);
+    assert_eq!(muted.read(999), Some(&999));
+
+    assert_eq!(muted.pop(), Some(999));
+    assert_eq!(muted.len(), 999);
+
+    //a clone shares the tail, popping from it copies the fragment first and leaves the clone alone
+    let copy = muted.clone();
+    assert_eq!(muted.pop(), Some(998));
+    assert_eq!(copy.read(998), Some(&998));
+
+    //popping through a whole fragment takes its slot with it, then the inline values go
+    while muted.len() > 2 {
+        muted.pop();
+    }
+    assert_eq!(muted.fragment_count(), 0);
+    assert_eq!(muted.pop(), Some(1));
+    assert_eq!(muted.pop(), Some(0));
+    assert_eq!(muted.pop(), None);
+
+    println!("{} {}", muted.len(), copy.len());
+}
@@ -0,0 +1,45 @@
+//cargo run --example serde_roundtrip --features serde
+use muted::{FragmentedVec, PrimitiveMuted, RawBuf, UnionMuted, WithFragments};
+
+fn main() {
+    let mut muted = muted::generic::Muted::new(vec![1u64, 2, 3]);
+    let dropped = muted.push_vec_convert(vec![4, 5]);
+    muted.push_vec_convert(vec![6, 7, 8]);
+    muted.drop_fragment(dropped).unwrap();
+
+    //plain sequence, comes back with every element inline
+    let json = serde_json::to_string(&muted).unwrap();
+    println!("{}", json); // [1,2,3,6,7,8]
+    let flat: muted::generic::Muted<u64> = serde_json::from_str(&json).unwrap();
+    assert_eq!(flat.iter().collect::<Vec<_>>(), muted.iter().collect::<Vec<_>>());
+
+    //keeping the layout, the hole and both fragment boundaries survive
+    let json = serde_json::to_string(&WithFragments(&muted)).unwrap();
+    println!("{}", json); // [{"Val":1},{"Val":2},{"Val":3},"Hole",{"Fragment":[6,7,8]}]
+    let mut layout: WithFragments<muted::generic::Muted<u64>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&WithFragments(&layout.0)).unwrap(), json);
+    assert!(layout.0.insert_vec(3, vec![4, 5]).is_some());
+    println!("{}", layout.0); // [1, 2, 3, 4, 5, 6, 7, 8]
+
+    let mut primitive = PrimitiveMuted::new(vec![1u32, 2], true);
+    let id = primitive.push_vec(vec![3, 4]);
+    primitive.push_vec(vec![5]);
+    primitive.drop_vec(id).unwrap();
+    let json = serde_json::to_string(&WithFragments(&primitive)).unwrap();
+    let back: WithFragments<PrimitiveMuted<u32>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&WithFragments(&back.0)).unwrap(), json);
+    assert_eq!(serde_json::to_string(&back.0).unwrap(), "[1,2,5]");
+
+    let mut union = UnionMuted::new(vec![String::from("a")]);
+    let id = FragmentedVec::push_vec(&mut union, vec![String::from("b"), String::from("c")]);
+    FragmentedVec::push_vec(&mut union, vec![String::from("d")]);
+    union.drop_fragment(id).unwrap();
+    let json = serde_json::to_string(&WithFragments(&union)).unwrap();
+    let back: WithFragments<UnionMuted<String>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&WithFragments(&back.0)).unwrap(), json);
+    println!("{}", json);
+
+    let buf = RawBuf::new(2);
+    let back: RawBuf = serde_json::from_str(&serde_json::to_string(&buf).unwrap()).unwrap();
+    assert_eq!(back, buf);
+}
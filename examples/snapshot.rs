@@ -0,0 +1,42 @@
+use std::sync::mpsc;
+use std::thread;
+use muted::{Muted as Generic, MutedSnapshot};
+
+//readers get frozen views while the writer keeps appending and overwriting
+fn main() {
+    let mut muted: Generic<u64> = Generic::new(vec![1, 2, 3]);
+    muted.push_vec((4..1000).collect());
+
+    let (tx, rx) = mpsc::channel::<MutedSnapshot<u64>>();
+    let reader = thread::spawn(move || {
+        let mut seen = Vec::new();
+        for snapshot in rx {
+            let copy = snapshot.clone();
+            //every element of a snapshot is what the writer had at that moment
+            assert_eq!(snapshot.iter().count(), snapshot.len());
+            assert_eq!(copy.read(3), Some(&4));
+            seen.push((snapshot.len(), snapshot.iter().sum::<u64>()));
+        }
+        seen
+    });
+
+    let first = muted.snapshot();
+    tx.send(first.clone()).unwrap();
+    for round in 0..10u64 {
+        muted.write(3, 4 + round * 100).unwrap();
+        muted.write(3, 4).unwrap();
+        muted.push_vec((0..100).collect());
+        tx.send(muted.snapshot()).unwrap();
+    }
+    drop(tx);
+
+    //the writes above never reached the first snapshot
+    assert_eq!(first.len(), 999);
+    assert_eq!(first.iter().sum::<u64>(), (1..1000).sum::<u64>());
+    muted.write(0, 100).unwrap();
+    assert_eq!(first.read(0), Some(&1));
+
+    for (len, sum) in reader.join().unwrap() {
+        println!("{} {}", len, sum);
+    }
+}
@@ -0,0 +1,44 @@
+use muted::{MutedBuilder, MutedStats};
+
+fn report(name: &str, stats: MutedStats) {
+    println!(
+        "{name}: {} elements in {} slots, {} fragments ({}..={} avg {:.1}), {} holes, {} bytes ({} slots, {} fragments, {} bookkeeping, {} prefix, {} marker)",
+        stats.len, stats.slots, stats.fragments, stats.min_fragment_len, stats.max_fragment_len, stats.avg_fragment_len,
+        stats.holes, stats.heap_bytes(), stats.slot_bytes, stats.fragment_bytes, stats.bookkeeping_bytes,
+        stats.prefix_bytes, stats.marker_bytes,
+    );
+}
+
+//the same workload on all three containers, then what each of them holds
+fn main() {
+    let mut generic = MutedBuilder::<u64>::new().build(vec![0; 10]);
+    let mut primitive = MutedBuilder::<u64>::new().build_primitive(vec![0; 10]);
+    let mut union = MutedBuilder::<u64>::new().build_union(vec![0; 10]);
+    for f in 1..=20 {
+        generic.push_vec(vec![f; f as usize]);
+        primitive.push_vec(vec![f; f as usize]);
+        union.push_vec_convert(vec![f; f as usize]);
+    }
+    for slot in [10, 12, 14] {
+        generic.take_fragment(slot).unwrap();
+        primitive.take_fragment(slot).unwrap();
+        union.take_fragment(slot).unwrap();
+    }
+
+    for stats in [generic.stats(), primitive.stats(), union.stats()] {
+        assert_eq!((stats.len, stats.fragments, stats.holes, stats.slots), (211, 17, 3, 30));
+        assert_eq!((stats.min_fragment_len, stats.max_fragment_len), (2, 20));
+        assert!(stats.fragment_bytes >= (210 - 1 - 3 - 5) * 8);
+    }
+    assert_eq!(generic.fragment_count(), 17);
+    assert_eq!(primitive.hole_count(), 3);
+    assert!(union.capacity() >= 30);
+
+    report("generic", generic.stats());
+    report("primitive", primitive.stats());
+    report("union", union.stats());
+
+    generic.set_arena(true);
+    report("arena", generic.stats());
+    println!("{} {} {}", generic.heap_bytes(), primitive.heap_bytes(), union.heap_bytes());
+}
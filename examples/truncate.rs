@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use muted::{MutedIn, PrimitiveMuted, UnionMuted};
+
+//truncate cuts through every kind of fragment, clear and shrink_to_fit keep the container usable
+fn main() {
+    let borrowed: Vec<u32> = (100..110).collect();
+    let mut muted: MutedIn<u32> = MutedIn::new(vec![0, 1]);
+    muted.push_vec((2..10).collect());
+    muted.push_shared(Arc::from((10..20).collect::<Vec<u32>>()));
+    muted.push_slice(&borrowed);
+    let copy = muted.clone();
+
+    muted.truncate(25);
+    assert_eq!(muted.iter().copied().collect::<Vec<_>>(), (0..20).chain(100..105).collect::<Vec<_>>());
+    muted.truncate(15);
+    assert_eq!(muted.len(), 15);
+    //the owned fragment is still shared with copy, cutting it copies what is left
+    muted.truncate(5);
+    assert_eq!(muted.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    assert_eq!(copy.len(), 30);
+    assert_eq!(copy.read(9), Some(&9));
+
+    muted.reserve(100);
+    assert!(muted.capacity() >= 100);
+    muted.shrink_to_fit();
+    muted.push_vec(vec![5, 6]);
+    assert_eq!(muted.read(6), Some(&6));
+    muted.clear();
+    assert!(muted.is_empty());
+    assert_eq!(muted.fragment_count(), 0);
+
+    let mut primitive = PrimitiveMuted::new(vec![0u64, 1], false);
+    primitive.push_vec((2..10).collect());
+    primitive.truncate(4);
+    primitive.shrink_to_fit();
+    assert_eq!(primitive.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+
+    let mut union = UnionMuted::new(vec![String::from("a")]);
+    union.push_vec_convert(vec![String::from("b"), String::from("c"), String::from("d")]);
+    union.truncate(2);
+    assert_eq!(union.iter().cloned().collect::<Vec<_>>(), vec!["a", "b"]);
+    union.clear();
+
+    println!("{} {} {} {}", muted.len(), copy.len(), primitive.len(), union.len());
+}
@@ -1,35 +1,74 @@
 
-use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
 use std::mem::ManuallyDrop;
+use std::ops::Range;
 use std::ptr;
 use std::ptr::NonNull;
-use crate::raw_buf::{offset, PrivateTypes, RawBuf, BIT};
+use std::slice;
+use crate::allocator::{box_in, map_in, vec_in, ABox, AMap, AVec, Allocator, Global};
+use crate::fragmented::{CompactPolicy, FragmentId, FragmentRef, FragmentedVec, MutedStats};
+use crate::prefix_index::{FenwickIndex, PrefixIndex};
+use crate::raw_buf::RawBuf;
 use crate::raw_buf::offset::Bit;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "serde")]
+use crate::serialize::{Slot, SlotRef, WithFragments};
 
 //#[derive(Debug, Eq, Hash, PartialEq)]
 
 #[repr(C, align(8))]
 pub union Data<T: Eq + Hash> {
     val: ManuallyDrop<T>,
-    rp: Option<NonNull<Vec<Data<T>>>>,
+    rp: Option<NonNull<Vec<T>>>,
 }
 
+//the slots are the only place Data is used, fragments are plain Vec<T> the same as in generic::Muted
+//everything a user can get their hands on (muted_from, fragment) is a value, rp only lives in the private slots
+impl<T: Eq + Hash> Data<T> {
+    pub fn get(&self) -> &T {
+        unsafe { &self.val }
+    }
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { &mut self.val }
+    }
+}
+
+//same as generic::Data, rp is only ever followed through the Muted that owns it
+unsafe impl<T: Eq + Hash + Send> Send for Data<T> {}
+unsafe impl<T: Eq + Hash + Sync> Sync for Data<T> {}
+
 const VALUE: u8 = 2;
 const RP: u8 = 1;
+const HOLE: u8 = 0;
+
 
 
+//(fragment, slot, len, generation)
+type Held<T, A> = (ManuallyDrop<ABox<Vec<T>, A>>, usize, usize, u32);
 
 //#[derive(Debug, Eq, PartialEq)]
-pub struct Muted<T: Hash + Eq + Debug> {
-    data: Box<Vec<Data<T>>>,
-    r_hold: HashMap<usize, Option<(ManuallyDrop<Box<Vec<Data<T>>>>, usize, usize)>>,
-    prefix_vec: (Vec<usize>, usize),
-    pub variant_marker: RawBuf,
+//A works the same as in generic::MutedIn, slots, fragment boxes, r_hold and the marker come from it
+pub struct Muted<T: Hash + Eq + Debug, A: Allocator + Clone = Global> {
+    data: ABox<AVec<Data<T>, A>, A>,
+    r_hold: AMap<usize, Option<Held<T, A>>, A>,
+    prefix_vec: (FenwickIndex, usize),
+    pub variant_marker: RawBuf<A>,
     rc: usize,
+    generation: u32,
+    //(policy, drops since the last compact)
+    compaction: (CompactPolicy, usize),
+    alloc: A,
 }
 
+//same reasoning as generic::Muted, the fragments are owned through r_hold
+//Sync since everything behind &self (read, iter, fragment) only hands out shared references, get_raw_mut is unsafe
+unsafe impl<T: Hash + Eq + Debug + Send, A: Allocator + Clone + Send> Send for Muted<T, A> {}
+unsafe impl<T: Hash + Eq + Debug + Sync, A: Allocator + Clone + Sync> Sync for Muted<T, A> {}
+
 #[macro_export]
 macro_rules! muted_nightly {
     () => {
@@ -64,19 +103,47 @@ impl<T: Eq + Hash + Debug + Clone> Iterator for Muted<T>{
 
  */
 
-impl<T: Eq + Hash + Debug> Drop for Muted<T>{
-    fn drop(&mut self) {
-        let mut to_drop: Vec<(NonNull<Vec<Data<T>>>, usize)> = Vec::new();
-        for (idx, item) in self.data.iter_mut().enumerate() {
-            if self.variant_marker.read_bit(idx as u32) == RP {
-                unsafe {
-                    let ptr = item as *mut Data<T> as *mut Vec<Data<T>>;
-                    to_drop.push((NonNull::new_unchecked(ptr), idx));
-                }
+pub struct Iter<'a, T: Eq + Hash + Debug, A: Allocator + Clone = Global> {
+    muted: &'a Muted<T, A>,
+    slot: usize,
+    inner: slice::Iter<'a, T>,
+}
+
+impl<'a, T: Eq + Hash + Debug, A: Allocator + Clone> Iterator for Iter<'a, T, A>{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let muted = self.muted;
+        loop {
+            if let Some(v) = self.inner.next() {
+                return Some(v);
+            }
+            if self.slot >= muted.data.len() {
+                return None;
+            }
+            let slot = self.slot;
+            self.slot += 1;
+            let item = &muted.data[slot];
+            match muted.variant(slot) {
+                VALUE => return Some(unsafe { &*item.val }),
+                RP => if let Some(p) = unsafe { item.rp } {
+                    self.inner = unsafe { &*p.as_ptr() }.iter();
+                },
+                _ => (),
             }
         }
-        for (ptr, _) in to_drop {
-            self.drop_vec(None, Some(ptr));
+    }
+}
+
+impl<T: Eq + Hash + Debug, A: Allocator + Clone> Drop for Muted<T, A>{
+    fn drop(&mut self) {
+        //drop_vec below must not kick off a compact halfway through
+        self.compaction.0 = CompactPolicy::Manual;
+        for idx in 0..self.data.len() {
+            match self.variant(idx) {
+                VALUE => unsafe { ManuallyDrop::drop(&mut self.data[idx].val) },
+                RP => { self.drop_vec(Some(idx), None); },
+                _ => (),
+            }
         }
         self.r_hold.clear();
         self.data.clear();
@@ -86,81 +153,24 @@ impl<T: Eq + Hash + Debug> Drop for Muted<T>{
     }
 }
 
-impl<T: Eq + Hash + Debug> Display for Muted<T>{
+impl<T: Eq + Hash + Debug, A: Allocator + Clone> Display for Muted<T, A>{
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         /*
         if f.alternate() {
             write!(f, "{:?}", self.data).expect("data not available");
         }
          */
-        let m_vec = Some(&self.data);
-        if let Some(m_vec) = m_vec {
-            let mut display_vec: Vec<&T> = Vec::new();
-            for (i,v) in m_vec.iter().enumerate(){
-                let start = (i as u32) * 2;
-                let end   = start + 2;
-                let variant = match self.variant_marker.read_bits(start, end, false, 0u8).unwrap() {
-                    PrivateTypes::U8(variant) => variant,
-                    _ => unreachable!()
-                };
-                if variant == VALUE{
-                    let v = unsafe { &*v.val };
-                    display_vec.push(v);
-                }
-                if variant == RP{
-                        unsafe {
-                            let p = v.rp.as_ref().expect("rp is none");
-                            let vec: &Vec<Data<T>> = p.as_ref();
-                            for (_, v) in vec.iter().enumerate(){
-                                let start = (i as u32 * 2) + 2;
-                                let end   = start + 2;
-                                let variant = match self.variant_marker.read_bits(start, end, false, 0u8).unwrap() {
-                                    PrivateTypes::U8(variant) => variant,
-                                    _ => unreachable!()
-                                };
-                                let is_val = variant == VALUE;
-                                if is_val {
-                                    let v = unsafe { &*v.val };
-                                    display_vec.push(v);
-                                }
-                            }
-                    }
-                }
-            }
-            write!(f, "{:?}", display_vec)
-        }else {
-            panic!("data not available");
-        }
+        let display_vec: Vec<&T> = self.iter().collect();
+        write!(f, "{:?}", display_vec)
     }
 }
 
 impl<T: Hash + Eq + Debug> Muted<T>{
     pub fn new(vec: Vec<T>) -> Self{
-        let len = vec.len();
-        let mut buf = RawBuf::new((len as u32 / 8u32) * 2u32 + 2);
-        for _ in 0..len{
-            buf.write_bits(offset::Bit(0), 2, 2, true);
-        }
-
-        return Muted{
-            data: Box::new(Self::muted_from(vec)),
-            r_hold: HashMap::new(),
-            prefix_vec: ((1..=len).collect(), len),
-            variant_marker: buf,
-            rc: 0
-        };
+        Self::from_slots_in(Self::muted_from(vec), Global)
     }
     pub fn new_no_conv(vec: Vec<Data<T>>) -> Self{
-        let len = vec.len();
-        let mut buf = RawBuf::new((len as u32 / 8u32) * 2u32 + 2);
-        buf.write_bits(offset::Bit(0), 2, 2, true);
-        return Muted{
-            data: Box::new(vec),
-            r_hold: HashMap::new(),
-            prefix_vec: ((1..=len).collect(), len),
-            variant_marker: buf,
-            rc: 0
-        };
+        Self::from_slots_in(vec, Global)
     }
 
     pub fn muted_from(other: Vec<T>) -> Vec<Data<T>>{
@@ -173,38 +183,184 @@ impl<T: Hash + Eq + Debug> Muted<T>{
             unsafe { ptr::write(dst.add(i), Data { val: ManuallyDrop::new(item) }) ; }
         }
         unsafe { new.set_len(len); }
-        return new;
+        new
+    }
+    //reverse of muted_from
+    pub fn muted_into(other: Vec<Data<T>>) -> Vec<T>{
+        other.into_iter().map(|item| ManuallyDrop::into_inner(unsafe { item.val })).collect()
+    }
+}
+
+impl<T: Hash + Eq + Debug, A: Allocator + Clone> Muted<T, A>{
+    //like new but the slots, fragment boxes, r_hold and the marker come from alloc
+    pub fn new_in(vec: Vec<T>, alloc: A) -> Self{
+        let mut slots = vec_in(vec.len(), alloc.clone());
+        slots.extend(vec.into_iter().map(|item| Data { val: ManuallyDrop::new(item) }));
+        Self::from_slots_in(slots, alloc)
+    }
+    fn from_slots_in(vec: AVec<Data<T>, A>, alloc: A) -> Self{
+        let len = vec.len();
+        let mut buf = RawBuf::new_in((len as u32 / 8u32) * 2u32 + 2, alloc.clone());
+        buf.fill_pairs(len, VALUE);
+        Muted{
+            data: box_in(vec, alloc.clone()),
+            r_hold: map_in(0, alloc.clone()),
+            prefix_vec: (FenwickIndex::from_lens(vec![1; len]), 0),
+            variant_marker: buf,
+            rc: 0,
+            generation: 0,
+            compaction: (CompactPolicy::Manual, 0),
+            alloc,
+        }
     }
-    pub fn push_vec_convert(&mut self, other: Vec<T>){
-        let other = Self::muted_from(other);
-        self.push_vec(other);
+    pub fn allocator(&self) -> &A{
+        &self.alloc
     }
-    pub fn push_vec(&mut self, other: Vec<Data<T>>) {
+    //fragments keep the vec as it is so this is O(1)
+    pub fn push_vec_convert(&mut self, other: Vec<T>) -> FragmentId{
         let len = other.len();
-        let mut other = ManuallyDrop::new(Box::new(other));
-        let ptr: &mut Vec<Data<T>> = &mut **other;
-        let ptr_hash = ptr as *mut Vec<Data<T>> as usize;
+        let mut other = ManuallyDrop::new(box_in(other, self.alloc.clone()));
+        let ptr: &mut Vec<T> = &mut other;
+        let ptr_hash = ptr as *mut Vec<T> as usize;
         let maybe_ptr = NonNull::new(ptr);
-        self.r_hold.insert(ptr_hash, Some((other, self.data.len(), len)));
+        let slot = self.data.len();
+        let id = self.next_id(slot);
+        self.r_hold.insert(ptr_hash, Some((other, slot, len, id.generation())));
         self.data.push(Data { rp: maybe_ptr });
         self.rc += 1;
-        self.variant_marker.write_bits(Bit(0), 1, 2, true);
-        for _ in 0..len{
-            self.variant_marker.write_bits(Bit(0), 2, 2 as u32, true);
-        }
-
-        let last = self.prefix_vec.0.last().cloned().unwrap_or(0);
-        self.prefix_vec.0.push(last + len);
-        self.prefix_vec.1 = self.prefix_vec.0.len();
+        self.mark(slot, RP);
+        self.prefix_vec.0.push(len);
+        id
+    }
+    //the values are unwrapped out of their Data first, O(n), push_vec_convert takes a Vec<T> as it is
+    pub fn push_vec(&mut self, other: Vec<Data<T>>) -> FragmentId{
+        self.push_vec_convert(Muted::<T>::muted_into(other))
     }
     pub fn is_empty(&self) -> bool{
-        self.data.is_empty()
+        self.len() == 0
     }
+    //only live elements, holes left by drop_vec count as zero
     pub fn len(&self) -> usize {
-        *self.prefix_vec.0.last().unwrap()
+        self.prefix_vec.0.total()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, A> {
+        Iter{
+            muted: self,
+            slot: 0,
+            inner: [].iter(),
+        }
+    }
+
+    pub fn compact(&mut self){
+        let slots = std::mem::replace(&mut *self.data, vec_in(0, self.alloc.clone()));
+        let mut merged: Vec<T> = Vec::with_capacity(self.len());
+        for (idx, slot) in slots.into_iter().enumerate() {
+            match self.variant(idx) {
+                VALUE => merged.push(ManuallyDrop::into_inner(unsafe { slot.val })),
+                RP => if let Some(ptr) = unsafe { slot.rp } {
+                    if let Some(Some(held)) = self.r_hold.remove(&(ptr.as_ptr() as usize)) {
+                        merged.extend(*ManuallyDrop::into_inner(held.0));
+                    }
+                },
+                _ => (),
+            }
+        }
+        self.rc = 0;
+        self.compaction.1 = 0;
+        self.prefix_vec.0.clear();
+        self.variant_marker = RawBuf::new_in(1, self.alloc.clone());
+        //nothing left means no fragment, not an empty one
+        if !merged.is_empty() {
+            self.push_vec_convert(merged);
+        }
+    }
+
+    pub fn drop_fragment(&mut self, id: FragmentId) -> Option<()>{
+        self.held(id)?;
+        self.drop_vec(Some(id.slot()), None)
+    }
+
+    //cheap move out to match the cheap copy in, leaves a hole just like drop_vec
+    pub fn take_fragment(&mut self, at: impl Into<FragmentRef>) -> Option<Vec<T>>{
+        let slot = match at.into() {
+            FragmentRef::Slot(slot) => slot,
+            FragmentRef::Id(id) => self.held(id)?.1,
+        };
+        if slot >= self.data.len() || self.variant(slot) != RP {
+            return None;
+        }
+        let key = unsafe { self.data[slot].rp }?.as_ptr() as usize;
+        let held = self.r_hold.remove(&key)??;
+        self.data[slot] = Data {rp: None};
+        self.rc -= 1;
+        self.prefix_vec.0.set(slot, 0);
+        let boxed: ABox<Vec<T>, A> = ManuallyDrop::into_inner(held.0);
+        let taken = *boxed;
+        self.dropped();
+        Some(taken)
+    }
+
+    pub fn fragment(&self, id: FragmentId) -> Option<&[T]>{
+        let held = self.held(id)?;
+        Some(held.0.as_slice())
     }
 
-    pub fn drop_vec(&mut self, index: Option<usize>, maybe_ptr: Option<NonNull<Vec<Data<T>>>>) -> Option<()>{
+    pub fn fragment_mut(&mut self, id: FragmentId) -> Option<&mut [T]>{
+        self.held(id)?;
+        let key = unsafe { self.data[id.slot()].rp }?.as_ptr() as usize;
+        match self.r_hold.get_mut(&key) {
+            Some(Some(held)) => Some(held.0.as_mut_slice()),
+            _ => None,
+        }
+    }
+
+    pub fn fragment_range(&self, id: FragmentId) -> Option<Range<usize>>{
+        let held = self.held(id)?;
+        let start = self.prefix_vec.0.prefix(held.1);
+        Some(start..start + held.2)
+    }
+
+    //the fragment behind id, if id still refers to the fragment it was issued for
+    fn held(&self, id: FragmentId) -> Option<&Held<T, A>>{
+        if id.slot() >= self.data.len() || self.variant(id.slot()) != RP {
+            return None;
+        }
+        let key = unsafe { self.data[id.slot()].rp }?.as_ptr() as usize;
+        match self.r_hold.get(&key) {
+            Some(Some(held)) if held.3 == id.generation() => Some(held),
+            _ => None,
+        }
+    }
+
+    fn next_id(&mut self, slot: usize) -> FragmentId{
+        self.generation = self.generation.wrapping_add(1);
+        FragmentId::new(slot, self.generation)
+    }
+
+    //bits are worked out in u64, RawBuf only takes u32 offsets once they are known to be inside it
+    fn variant(&self, slot: usize) -> u8 {
+        let bit = slot as u64 * 2;
+        if bit + 2 > self.variant_marker.len as u64 {
+            return HOLE;
+        }
+        let bit = bit as u32;
+        (self.variant_marker.read_bit(bit) << 1) | self.variant_marker.read_bit(bit + 1)
+    }
+
+    fn mark(&mut self, slot: usize, variant: u8) {
+        let bit = slot as u64 * 2;
+        if bit + 2 > self.variant_marker.len as u64 {
+            //RawBuf counts its bits in a u32
+            let max_bytes = (u32::MAX / 8) as usize;
+            assert!(bit / 8 < max_bytes as u64, "more slots than the variant marker can tag");
+            let grow = self.variant_marker.data.len().max(1).min(max_bytes - self.variant_marker.data.len());
+            self.variant_marker.extend_by(grow);
+        }
+        self.variant_marker.write_bits(Bit(bit), variant as u64, 2, false);
+    }
+
+    pub fn drop_vec(&mut self, index: Option<usize>, maybe_ptr: Option<NonNull<Vec<T>>>) -> Option<()>{
         match (index, maybe_ptr) {
             (Some(_),Some(_)) => {
                 panic!("choose either index or direct pointer");
@@ -214,91 +370,197 @@ impl<T: Hash + Eq + Debug> Muted<T>{
             }
             _ => (),
         }
-        let mut real_index = None;
-        if let Some(key_index) = self.r_hold.get_key_value(&(maybe_ptr.unwrap().as_ptr() as *mut _ as usize)){
-            if let Some(x) = key_index.1{
-                real_index = Some(x.1);
+        let key = match (index, maybe_ptr) {
+            (_, Some(ptr)) => ptr.as_ptr() as usize,
+            (Some(index), None) => {
+                if index >= self.data.len() || self.variant(index) != RP {
+                    return None;
+                }
+                unsafe { self.data[index].rp }?.as_ptr() as usize
             }
+            _ => unreachable!(),
+        };
+        let held = self.r_hold.remove(&key)??;
+        self.data[held.1] = Data {rp: None};
+        self.prefix_vec.0.set(held.1, 0);
+        std::mem::drop(ManuallyDrop::into_inner(held.0));
+        self.rc -= 1;
+        self.dropped();
+        Some(())
+    }
+
+    //see CompactPolicy, Manual (the default) never compacts on its own
+    pub fn set_compact_policy(&mut self, policy: CompactPolicy){
+        self.compaction = (policy, 0);
+    }
+
+    fn dropped(&mut self){
+        self.compaction.1 += 1;
+        if self.compaction.0.due(self.compaction.1) {
+            self.compact();
         }
-        if let Some(u_index) = index{
-            real_index = Some(u_index);
-        }
-
-        if let Some(held) = self.r_hold.remove(&(maybe_ptr.unwrap().as_ptr() as *mut _ as usize)) {
-            self.data[real_index.unwrap()] = Data {rp: None};
-            let boxed: Box<Vec<Data<T>>> = ManuallyDrop::into_inner(match held {
-                Some(x) => x.0,
-                None => unreachable!(),
-            });
-            std::mem::drop(boxed);
-            self.rc -= 1;
-            return Some(());
-        }
-        if real_index.is_some() && !self.data.is_empty(){
-            let mut ptr= if self.variant_marker.read_bit(real_index.unwrap() as u32) == RP {
-                &unsafe { self.data[real_index.unwrap()].rp }
-            }else { 
-                return None
-            };
-            
-            if let Some(held) = self.r_hold.remove(&(ptr.unwrap().as_ptr() as *mut _ as usize)) {
-                let boxed: Box<Vec<Data<T>>> = ManuallyDrop::into_inner(match held {
-                    Some(x) => x.0,
-                    None => unreachable!(),
-                });
-
-                std::mem::drop(boxed);
-                self.rc -= 1;
-            }else {
-                return None;
+    }
+
+    //what MutedBuilder hands over, room for more slots, fragments and prefix entries
+    pub(crate) fn reserve_for(&mut self, slots: usize, fragments: usize, prefix: usize){
+        self.data.reserve(slots);
+        self.r_hold.reserve(fragments);
+        self.prefix_vec.0.reserve(prefix);
+    }
+
+    //room for additional more slots and prefix entries, fragments bring their own elements
+    pub fn reserve(&mut self, additional: usize){
+        self.reserve_for(additional, 0, additional);
+    }
+
+    //gives back what slots, bookkeeping and fragments hold on top of what they use
+    pub fn shrink_to_fit(&mut self){
+        for held in self.r_hold.values_mut().flatten() {
+            held.0.shrink_to_fit();
+        }
+        self.data.shrink_to_fit();
+        self.r_hold.shrink_to_fit();
+        self.prefix_vec.0.shrink_to_fit();
+        self.variant_marker.data.shrink_to_fit();
+    }
+
+    //like Vec::truncate, whole slots past len go and the fragment len lands in is shortened
+    pub fn truncate(&mut self, len: usize){
+        if len >= self.len() {
+            return;
+        }
+        while let Some(last) = self.data.len().checked_sub(1) {
+            if self.prefix_vec.0.prefix(last) < len {
+                break;
+            }
+            let mut slot = self.data.pop().unwrap();
+            let variant = self.variant(last);
+            self.mark(last, HOLE);
+            match variant {
+                VALUE => unsafe { ManuallyDrop::drop(&mut slot.val) },
+                RP => if let Some(ptr) = unsafe { slot.rp } {
+                    if let Some(Some(held)) = self.r_hold.remove(&(ptr.as_ptr() as usize)) {
+                        std::mem::drop(ManuallyDrop::into_inner(held.0));
+                        self.rc -= 1;
+                    }
+                },
+                _ => (),
+            }
+        }
+        self.prefix_vec.0.truncate(self.data.len());
+        let last = match self.data.len().checked_sub(1) {
+            Some(last) => last,
+            None => return,
+        };
+        let keep = len - self.prefix_vec.0.prefix(last);
+        if keep >= self.prefix_vec.0.get(last) {
+            return;
+        }
+        //a VALUE is 1 long and a hole 0 so only a fragment can be cut in two
+        if let Some(ptr) = unsafe { self.data[last].rp } {
+            if let Some(Some(held)) = self.r_hold.get_mut(&(ptr.as_ptr() as usize)) {
+                held.0.truncate(keep);
+                held.2 = keep;
             }
-            self.data[real_index.unwrap()] = Data {rp: None};
-            return Some(());
-        }else {
-            return None;
         }
+        self.prefix_vec.0.set(last, keep);
     }
-    
-    /*
-    pub unsafe fn insert_vec_unchecked(&mut self, index: usize, other: Vec<T>) -> Option<()>{
-        self.insert_vec_inner(index, other, true)
+
+    //drops every element and fragment, the slot capacity stays
+    pub fn clear(&mut self){
+        for idx in 0..self.data.len() {
+            match self.variant(idx) {
+                VALUE => unsafe { ManuallyDrop::drop(&mut self.data[idx].val) },
+                RP => if let Some(ptr) = unsafe { self.data[idx].rp } {
+                    if let Some(Some(held)) = self.r_hold.remove(&(ptr.as_ptr() as usize)) {
+                        std::mem::drop(ManuallyDrop::into_inner(held.0));
+                    }
+                },
+                _ => (),
+            }
+        }
+        self.data.clear();
+        self.r_hold.clear();
+        self.prefix_vec.0.clear();
+        self.prefix_vec.1 = 0;
+        self.variant_marker = RawBuf::new_in(1, self.alloc.clone());
+        self.rc = 0;
+    }
+
+    pub fn fragment_count(&self) -> usize{
+        self.rc
+    }
+
+    //a hole is an RP slot with no pointer left in it
+    pub fn hole_count(&self) -> usize{
+        (0..self.data.len()).filter(|slot| self.variant(*slot) == RP && unsafe { self.data[*slot].rp }.is_none()).count()
+    }
+
+    //in slots, a fragment takes one however long it is
+    pub fn capacity(&self) -> usize{
+        self.data.capacity()
     }
 
-    pub fn insert_vec(&mut self, index: usize, other: Vec<T>) -> Option<()>{
+    pub fn heap_bytes(&self) -> usize{
+        self.stats().heap_bytes()
+    }
+
+    //O(slots)
+    pub fn stats(&self) -> MutedStats{
+        let fragments = || self.r_hold.values().flatten().map(|held| &**held.0);
+        let mut stats = MutedStats { len: self.len(), slots: self.data.len(), holes: self.hole_count(), ..MutedStats::default() };
+        stats.count_fragments(fragments().map(Vec::len));
+        stats.slot_bytes = self.data.capacity() * size_of::<Data<T>>();
+        stats.fragment_bytes = fragments().map(|vec| vec.capacity() * size_of::<T>()).sum();
+        stats.bookkeeping_bytes = self.r_hold.capacity()
+            * (size_of::<usize>() + size_of::<Option<Held<T, A>>>() + 1)
+            + self.rc * size_of::<Vec<T>>();
+        stats.prefix_bytes = self.prefix_vec.0.heap_bytes();
+        stats.marker_bytes = self.variant_marker.data.capacity();
+        stats
+    }
+
+    //insert_vec without the bounds check
+    /// # Safety
+    ///
+    /// `index` has to be below the number of slots. Past the end the tag reads as a hole so this gives None for now,
+    /// later versions may index the slots without checking at all.
+    pub unsafe fn insert_vec_unchecked(&mut self, index: usize, other: Vec<T>) -> Option<FragmentId>{
+        self.insert_vec_inner(index, other)
+    }
+
+    pub fn insert_vec(&mut self, index: usize, other: Vec<T>) -> Option<FragmentId>{
         if index >= self.data.len() { return None; }
-        self.insert_vec_inner(index, other, false)
+        self.insert_vec_inner(index, other)
     }
-    
-    
-    fn insert_vec_inner(&mut self, index: usize, vec: Vec<T>, skip_calibration: bool) -> Option<()>{
-        let len = vec.len();
-        let wrapped: Vec<Data<T>> = vec.into_iter().map(Data{_: val}).collect();
-        let mut other = ManuallyDrop::new(Box::new(wrapped));
-        let ptr= NonNull::new(&mut **other);
-        if self.data[index] == Data::Rp(None) {
-            self.r_hold.insert(Data::Rp(ptr), Some((other, index, len)));
-            self.data[index] = Data::Rp(ptr);
-        }else {
-            {
-                ManuallyDrop::into_inner(other);
-            }
+
+    fn insert_vec_inner(&mut self, index: usize, vec: Vec<T>) -> Option<FragmentId>{
+        if self.variant(index) != RP || unsafe { self.data[index].rp }.is_some() {
             return None;
         }
+        let len = vec.len();
+        let mut other = ManuallyDrop::new(box_in(vec, self.alloc.clone()));
+        let ptr = NonNull::from(&mut **other);
+        let id = self.next_id(index);
+        self.r_hold.insert(ptr.as_ptr() as usize, Some((other, index, len, id.generation())));
+        self.data[index] = Data {rp: Some(ptr)};
         self.rc += 1;
-        if !skip_calibration{
-            unsafe {self.calibrate_index(len)}
-        }
-        return Some(());
+        self.prefix_vec.0.set(index, len);
+        Some(id)
     }
-    
-     */
+
+    //only ever takes shared references so reads from several threads at once are fine, see the Sync impl
     pub fn read(&self, index: usize) -> Option<&T>{
-        unsafe {
-            if let Some(immutable) = self.get_raw_mut(index) {
-                return Some(immutable.as_ref())
-            }else {
-                return None;
-            }
+        let (rough_index, offset) = self.prefix_vec.0.find(index)?;
+        match self.variant(rough_index) {
+            VALUE => Some(self.data[rough_index].get()),
+            _ => unsafe { self.data[rough_index].rp }.map(|pointer| {
+                let vec: &Vec<T> = unsafe { pointer.as_ref() };
+                match vec.get(offset) {
+                    None => panic!("read failed, index is out of bounds, index is {}, len is: {}", offset, vec.len()),
+                    Some(x) => x,
+                }
+            }),
         }
     }
     pub fn write(&mut self, index: usize, val: T) -> Option<()>{
@@ -306,19 +568,19 @@ impl<T: Hash + Eq + Debug> Muted<T>{
             if let Some(mutable) = self.get_raw_mut(index) {
                 mutable.replace(val);
 
-                return Some(());
+                Some(())
             }else {
-                return None;
+                None
             }
         }
     }
+    /// # Safety
+    ///
+    /// The pointer is made through `&self` but is meant for writing. Nothing else may read or write the element
+    /// while it is in use, so the caller needs the access `&mut self` would give it, the way `write` has it.
     pub unsafe fn get_raw_mut(&self, index: usize) -> Option<NonNull<T>>{
-        let target = index + 1;
-        let rough_index = self.prefix_vec.0.partition_point(|&x| x < target);
-        if rough_index >= self.data.len(){
-            panic!("index out of bounds");
-        }
-        return if self.variant_marker.read_bit(rough_index as u32) == RP {
+        let (rough_index, offset) = self.prefix_vec.0.find(index)?;
+        if self.variant(rough_index) == VALUE {
             let v = &self.data[rough_index];
             Some(NonNull::from(unsafe { &*v.val }))
         } else {
@@ -326,37 +588,232 @@ impl<T: Hash + Eq + Debug> Muted<T>{
                 let p = unsafe {p.rp.as_ref()};
                 if let Some(pointer) = p{
                     unsafe {
-                        let vec: &mut Vec<Data<T>> = &mut *pointer.as_ptr();
-                        let offset = if rough_index == 0{
-                            index
-                        }else {
-                            index - self.prefix_vec.0[rough_index - 1]
-                        };
+                        let vec: &mut Vec<T> = &mut *pointer.as_ptr();
                         let len = vec.len();
-                        return match vec.get_mut(offset) {
+                        match vec.get_mut(offset) {
                             None => panic!("read or write failed, index is out of bounds, index is {}, len is: {}", offset, len),
-                            Some(x) => if self.variant_marker.read_bit(rough_index as u32) == RP {
-                                unreachable!("No nested pointers")
-                            }else {
-                                let v = unsafe {&mut x.val as *mut ManuallyDrop<T>};
-                                Some(NonNull::new_unchecked(v.cast()))
-                            }
+                            Some(x) => Some(NonNull::from(x)),
                         }
                     }
                 }else {
                     None
                 }
 
-        };
+        }
+    }
+
+}
+
+impl<T: Hash + Eq + Debug, A: Allocator + Clone> FragmentedVec<T> for Muted<T, A>{
+    type Iter<'a> = Iter<'a, T, A> where T: 'a, A: 'a;
+
+    fn len(&self) -> usize {
+        Muted::len(self)
+    }
+    fn read(&self, index: usize) -> Option<&T> {
+        Muted::read(self, index)
+    }
+    fn write(&mut self, index: usize, val: T) -> Option<()> {
+        Muted::write(self, index, val)
+    }
+    fn push_vec(&mut self, other: Vec<T>) -> FragmentId {
+        self.push_vec_convert(other)
+    }
+    fn drop_fragment(&mut self, id: FragmentId) -> Option<()> {
+        Muted::drop_fragment(self, id)
+    }
+    fn take_fragment(&mut self, at: impl Into<FragmentRef>) -> Option<Vec<T>> {
+        Muted::take_fragment(self, at)
+    }
+    fn insert_fragment(&mut self, slot: usize, other: Vec<T>) -> Option<FragmentId> {
+        self.insert_vec(slot, other)
+    }
+    fn fragment(&self, id: FragmentId) -> Option<&[T]> {
+        Muted::fragment(self, id)
+    }
+    fn fragment_mut(&mut self, id: FragmentId) -> Option<&mut [T]> {
+        Muted::fragment_mut(self, id)
+    }
+    fn fragment_range(&self, id: FragmentId) -> Option<Range<usize>> {
+        Muted::fragment_range(self, id)
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        Muted::iter(self)
+    }
+    fn compact(&mut self) {
+        Muted::compact(self)
+    }
+}
+
+//one unit of work for par_chunks, same as generic::Chunk, a run of inline values is still in its slots
+#[cfg(feature = "rayon")]
+pub enum Chunk<'a, T: Eq + Hash> {
+    //only ever VALUE slots
+    Slots(&'a [Data<T>]),
+    Elements(&'a [T]),
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Eq + Hash> Chunk<'a, T> {
+    pub fn len(&self) -> usize {
+        match self {
+            Chunk::Slots(slots) => slots.len(),
+            Chunk::Elements(elements) => elements.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
-    unsafe fn calibrate_index(&mut self, added_len: usize){
-        let added_len = added_len as usize;
-        let start = self.prefix_vec.1;
-        for i in start..self.prefix_vec.0.len(){
-            self.prefix_vec.0[i] += added_len;
+    pub fn iter(&self) -> ChunkIter<'a, T> {
+        match self {
+            Chunk::Slots(slots) => ChunkIter::Slots(slots.iter()),
+            Chunk::Elements(elements) => ChunkIter::Elements(elements.iter()),
         }
+    }
+}
+
+#[cfg(feature = "rayon")]
+pub enum ChunkIter<'a, T: Eq + Hash> {
+    Slots(slice::Iter<'a, Data<T>>),
+    Elements(slice::Iter<'a, T>),
+}
 
+#[cfg(feature = "rayon")]
+impl<'a, T: Eq + Hash> Iterator for ChunkIter<'a, T>{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ChunkIter::Slots(slots) => slots.next().map(Data::get),
+            ChunkIter::Elements(elements) => elements.next(),
+        }
     }
+}
 
+#[cfg(feature = "rayon")]
+enum ChunkMut<'a, T: Eq + Hash> {
+    Slots(slice::IterMut<'a, Data<T>>),
+    Elements(slice::IterMut<'a, T>),
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Eq + Hash> Iterator for ChunkMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ChunkMut::Slots(slots) => slots.next().map(Data::get_mut),
+            ChunkMut::Elements(elements) => elements.next(),
+        }
+    }
+}
+
+//work is split along slot boundaries, every chunk is either a whole fragment or a run of inline values
+#[cfg(feature = "rayon")]
+impl<T: Hash + Eq + Debug + Send + Sync, A: Allocator + Clone> Muted<T, A>{
+    pub fn par_chunks(&self) -> impl IndexedParallelIterator<Item = Chunk<'_, T>> {
+        let mut chunks: Vec<Chunk<'_, T>> = Vec::new();
+        let mut run = 0;
+        for slot in 0..self.data.len() {
+            if self.variant(slot) == VALUE {
+                continue;
+            }
+            if run < slot {
+                chunks.push(Chunk::Slots(&self.data[run..slot]));
+            }
+            run = slot + 1;
+            if let Some(p) = unsafe { self.data[slot].rp } {
+                chunks.push(Chunk::Elements(unsafe { &*p.as_ptr() }.as_slice()));
+            }
+        }
+        if run < self.data.len() {
+            chunks.push(Chunk::Slots(&self.data[run..]));
+        }
+        chunks.into_par_iter()
+    }
+
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = &T> {
+        self.par_chunks().flat_map_iter(|chunk| chunk.iter())
+    }
+
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut T> {
+        let variants: Vec<u8> = (0..self.data.len()).map(|slot| self.variant(slot)).collect();
+        let mut chunks: Vec<ChunkMut<'_, T>> = Vec::new();
+        let mut rest: &mut [Data<T>] = &mut self.data[..];
+        let mut slot = 0;
+        while !rest.is_empty() {
+            let run = variants[slot..].iter().take_while(|&&v| v == VALUE).count();
+            let (values, tail) = rest.split_at_mut(run);
+            if !values.is_empty() {
+                chunks.push(ChunkMut::Slots(values.iter_mut()));
+            }
+            slot += run;
+            match tail.split_first_mut() {
+                Some((item, tail)) => {
+                    //every fragment has its own box so the slices never overlap
+                    if let Some(p) = unsafe { item.rp } {
+                        chunks.push(ChunkMut::Elements(unsafe { &mut *p.as_ptr() }.iter_mut()));
+                    }
+                    slot += 1;
+                    rest = tail;
+                }
+                None => break,
+            }
+        }
+        chunks.into_par_iter().flat_map_iter(|chunk| chunk)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Hash + Eq + Debug + Serialize, A: Allocator + Clone> Serialize for Muted<T, A>{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Hash + Eq + Debug + Deserialize<'de>> Deserialize<'de> for Muted<T>{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<T>::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Hash + Eq + Debug + Serialize, A: Allocator + Clone> Serialize for WithFragments<&Muted<T, A>>{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let muted = self.0;
+        serializer.collect_seq((0..muted.data.len()).map(|slot| match muted.variant(slot) {
+            VALUE => SlotRef::Val(muted.data[slot].get()),
+            RP => match unsafe { muted.data[slot].rp } {
+                Some(p) => SlotRef::Fragment(unsafe { &*p.as_ptr() }.as_slice()),
+                None => SlotRef::Hole,
+            },
+            _ => SlotRef::Hole,
+        }))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Hash + Eq + Debug + Deserialize<'de>> Deserialize<'de> for WithFragments<Muted<T>>{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let slots = Vec::<Slot<T>>::deserialize(deserializer)?;
+        let mut muted = Muted::new(vec![]);
+        for slot in slots {
+            let index = muted.data.len();
+            match slot {
+                Slot::Val(val) => {
+                    muted.data.push(Data { val: ManuallyDrop::new(val) });
+                    muted.mark(index, VALUE);
+                    muted.prefix_vec.0.push(1);
+                }
+                Slot::Fragment(vec) => { muted.push_vec_convert(vec); }
+                Slot::Hole => {
+                    muted.data.push(Data { rp: None });
+                    muted.mark(index, RP);
+                    muted.prefix_vec.0.push(0);
+                }
+            }
+        }
+        Ok(WithFragments(muted))
+    }
 }
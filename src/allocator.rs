@@ -0,0 +1,78 @@
+//where the data box, the fragment boxes and r_hold get their memory from
+//with the nightly feature A can be any std::alloc::Allocator (arenas, bump allocators...), on stable the
+//parameter is still there so the types line up but Global is the only thing that does anything
+#[cfg(feature = "nightly")]
+pub use std::alloc::{Allocator, Global};
+#[cfg(feature = "nightly")]
+use std::collections::hash_map::RandomState;
+
+//stand in for std::alloc::Allocator, implementing it for anything else still allocates from the global heap
+#[cfg(not(feature = "nightly"))]
+pub trait Allocator {}
+
+#[cfg(not(feature = "nightly"))]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Global;
+
+#[cfg(not(feature = "nightly"))]
+impl Allocator for Global {}
+
+#[cfg(feature = "nightly")]
+pub(crate) type AVec<T, A> = Vec<T, A>;
+#[cfg(feature = "nightly")]
+pub(crate) type ABox<T, A> = Box<T, A>;
+//std's HashMap can't take an allocator, hashbrown's can
+#[cfg(feature = "nightly")]
+pub(crate) type AMap<K, V, A> = hashbrown::HashMap<K, V, RandomState, A>;
+
+#[cfg(feature = "nightly")]
+pub(crate) fn vec_in<T, A: Allocator>(capacity: usize, alloc: A) -> AVec<T, A> {
+    Vec::with_capacity_in(capacity, alloc)
+}
+
+#[cfg(feature = "nightly")]
+pub(crate) fn box_in<T, A: Allocator>(val: T, alloc: A) -> ABox<T, A> {
+    Box::new_in(val, alloc)
+}
+
+#[cfg(feature = "nightly")]
+pub(crate) fn map_in<K, V, A: Allocator>(capacity: usize, alloc: A) -> AMap<K, V, A> {
+    hashbrown::HashMap::with_capacity_and_hasher_in(capacity, RandomState::new(), alloc)
+}
+
+//the aliases have to mention A even when it doesn't matter, going through a trait keeps it used
+#[cfg(not(feature = "nightly"))]
+pub(crate) trait Storage {
+    type Vec<T>;
+    type Box<T>;
+    type Map<K, V>;
+}
+
+#[cfg(not(feature = "nightly"))]
+impl<A: Allocator> Storage for A {
+    type Vec<T> = Vec<T>;
+    type Box<T> = Box<T>;
+    type Map<K, V> = std::collections::HashMap<K, V>;
+}
+
+#[cfg(not(feature = "nightly"))]
+pub(crate) type AVec<T, A> = <A as Storage>::Vec<T>;
+#[cfg(not(feature = "nightly"))]
+pub(crate) type ABox<T, A> = <A as Storage>::Box<T>;
+#[cfg(not(feature = "nightly"))]
+pub(crate) type AMap<K, V, A> = <A as Storage>::Map<K, V>;
+
+#[cfg(not(feature = "nightly"))]
+pub(crate) fn vec_in<T, A: Allocator>(capacity: usize, _alloc: A) -> AVec<T, A> {
+    Vec::with_capacity(capacity)
+}
+
+#[cfg(not(feature = "nightly"))]
+pub(crate) fn box_in<T, A: Allocator>(val: T, _alloc: A) -> ABox<T, A> {
+    Box::new(val)
+}
+
+#[cfg(not(feature = "nightly"))]
+pub(crate) fn map_in<K, V, A: Allocator>(capacity: usize, _alloc: A) -> AMap<K, V, A> {
+    std::collections::HashMap::with_capacity(capacity)
+}
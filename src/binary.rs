@@ -0,0 +1,266 @@
+use std::io::{self, Read, Write};
+use std::mem;
+use std::slice;
+
+//on disk layout, everything little endian except the elements which are written as they sit in memory:
+//  header   magic[8] version:u16 elem_size:u16 flags:u32 slots:u64 len:u64        (32 bytes)
+//  table    one (kind:u64, offset:u64, len:u64) per slot, offset/len in elements   (24 bytes each)
+//  elements every slot's elements back to back in slot order, starts 8 byte aligned
+//  checksum word wise fnv-1a 64 over everything above                              (8 bytes)
+//offset is just prefix_vec at that slot so a reader can find any fragment without walking the table
+pub const MAGIC: [u8; 8] = *b"MUTEDBIN";
+pub const FORMAT_VERSION: u16 = 1;
+pub const HEADER_LEN: usize = 32;
+pub const SLOT_ENTRY_LEN: usize = 24;
+//the element bytes are native endian, a reader on the other endianness has to refuse them
+pub const FLAG_BIG_ENDIAN: u32 = 1;
+
+pub const SLOT_HOLE: u64 = 0;
+pub const SLOT_VAL: u64 = 1;
+pub const SLOT_FRAGMENT: u64 = 2;
+
+//plain old data, elements go to disk and come back as raw bytes
+/// # Safety
+///
+/// Every bit pattern of `size_of::<T>()` bytes, all zeroes included, has to be a valid value of the type and the
+/// type can't have padding bytes, they would be written out uninitialised. It can't hold pointers or references
+/// either, the bytes are read back in another process.
+pub unsafe trait Pod: Copy + 'static {}
+
+unsafe impl Pod for u8 {}
+unsafe impl Pod for u16 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for u64 {}
+unsafe impl Pod for i8 {}
+unsafe impl Pod for i16 {}
+unsafe impl Pod for i32 {}
+unsafe impl Pod for i64 {}
+
+pub(crate) fn as_bytes<T: Pod>(vals: &[T]) -> &[u8] {
+    unsafe { slice::from_raw_parts(vals.as_ptr() as *const u8, mem::size_of_val(vals)) }
+}
+
+fn as_bytes_mut<T: Pod>(vals: &mut [T]) -> &mut [u8] {
+    unsafe { slice::from_raw_parts_mut(vals.as_mut_ptr() as *mut u8, mem::size_of_val(vals)) }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn native_flags() -> u32 {
+    if cfg!(target_endian = "big") { FLAG_BIG_ENDIAN } else { 0 }
+}
+
+//fnv-1a but over little endian u64 words instead of single bytes, 8x fewer multiplies on big dumps
+//bytes are buffered until a word is full so it doesn't matter how the input is chunked
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Checksum {
+    state: u64,
+    pending: [u8; 8],
+    filled: usize,
+}
+
+impl Checksum {
+    pub(crate) fn new() -> Self {
+        Checksum { state: 0xcbf29ce484222325, pending: [0; 8], filled: 0 }
+    }
+
+    fn word(&mut self, word: u64) {
+        self.state ^= word;
+        self.state = self.state.wrapping_mul(0x100000001b3);
+    }
+
+    pub(crate) fn update(&mut self, mut bytes: &[u8]) {
+        if self.filled > 0 {
+            let take = (8 - self.filled).min(bytes.len());
+            self.pending[self.filled..self.filled + take].copy_from_slice(&bytes[..take]);
+            self.filled += take;
+            bytes = &bytes[take..];
+            if self.filled < 8 {
+                return;
+            }
+            self.word(u64::from_le_bytes(self.pending));
+            self.filled = 0;
+        }
+        let mut words = bytes.chunks_exact(8);
+        for word in &mut words {
+            self.word(u64::from_le_bytes(word.try_into().unwrap()));
+        }
+        let rest = words.remainder();
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.filled = rest.len();
+    }
+
+    //a partial last word is zero padded, the length in the header keeps that unambiguous
+    pub(crate) fn get(&self) -> u64 {
+        let mut sum = *self;
+        if sum.filled > 0 {
+            let mut last = [0u8; 8];
+            last[..sum.filled].copy_from_slice(&sum.pending[..sum.filled]);
+            sum.word(u64::from_le_bytes(last));
+        }
+        sum.state
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct Header {
+    pub(crate) elem_size: usize,
+    pub(crate) slots: usize,
+    pub(crate) len: usize,
+}
+
+impl Header {
+    pub(crate) fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut out = [0u8; HEADER_LEN];
+        out[0..8].copy_from_slice(&MAGIC);
+        out[8..10].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        out[10..12].copy_from_slice(&(self.elem_size as u16).to_le_bytes());
+        out[12..16].copy_from_slice(&native_flags().to_le_bytes());
+        out[16..24].copy_from_slice(&(self.slots as u64).to_le_bytes());
+        out[24..32].copy_from_slice(&(self.len as u64).to_le_bytes());
+        out
+    }
+
+    //checks everything a reader of T needs to trust the rest of the file
+    pub(crate) fn decode<T: Pod>(bytes: &[u8; HEADER_LEN]) -> io::Result<Header> {
+        if bytes[0..8] != MAGIC {
+            return Err(invalid("not a muted binary file"));
+        }
+        let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+        if version != FORMAT_VERSION {
+            return Err(invalid("unsupported format version"));
+        }
+        let elem_size = u16::from_le_bytes([bytes[10], bytes[11]]) as usize;
+        if elem_size != mem::size_of::<T>() {
+            return Err(invalid("element size doesn't match T"));
+        }
+        let flags = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
+        if flags != native_flags() {
+            return Err(invalid("written on a machine with the other endianness"));
+        }
+        let slots = u64::from_le_bytes(bytes[16..24].try_into().unwrap()) as usize;
+        let len = u64::from_le_bytes(bytes[24..32].try_into().unwrap()) as usize;
+        Ok(Header { elem_size, slots, len })
+    }
+}
+
+pub(crate) fn encode_slot(kind: u64, offset: usize, len: usize) -> [u8; SLOT_ENTRY_LEN] {
+    let mut out = [0u8; SLOT_ENTRY_LEN];
+    out[0..8].copy_from_slice(&kind.to_le_bytes());
+    out[8..16].copy_from_slice(&(offset as u64).to_le_bytes());
+    out[16..24].copy_from_slice(&(len as u64).to_le_bytes());
+    out
+}
+
+//(kind, offset, len), also makes sure the table is consistent with itself
+pub(crate) fn decode_slot(bytes: &[u8], expected_offset: usize) -> io::Result<(u64, usize, usize)> {
+    let kind = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+    let offset = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+    let len = u64::from_le_bytes(bytes[16..24].try_into().unwrap()) as usize;
+    let len_ok = match kind {
+        SLOT_HOLE => len == 0,
+        SLOT_VAL => len == 1,
+        SLOT_FRAGMENT => true,
+        _ => return Err(invalid("unknown slot kind")),
+    };
+    if !len_ok || offset != expected_offset {
+        return Err(invalid("corrupt slot table"));
+    }
+    Ok((kind, offset, len))
+}
+
+//used by write_to, keeps the running checksum of everything written so far
+pub(crate) struct FormatWriter<W: Write> {
+    out: io::BufWriter<W>,
+    sum: Checksum,
+}
+
+impl<W: Write> FormatWriter<W> {
+    pub(crate) fn new(out: W) -> Self {
+        FormatWriter { out: io::BufWriter::new(out), sum: Checksum::new() }
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.sum.update(bytes);
+        self.out.write_all(bytes)
+    }
+
+    pub(crate) fn elements<T: Pod>(&mut self, vals: &[T]) -> io::Result<()> {
+        self.bytes(as_bytes(vals))
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        let sum = self.sum.get().to_le_bytes();
+        self.out.write_all(&sum)?;
+        self.out.flush()
+    }
+}
+
+//how much FormatReader::elements reads before it grows the vec again
+const ELEMENT_CHUNK_BYTES: usize = 1 << 16;
+
+//reading side of FormatWriter, the checksum is only checked in finish so don't hand anything out before that
+pub(crate) struct FormatReader<R: Read> {
+    input: io::BufReader<R>,
+    sum: Checksum,
+}
+
+impl<R: Read> FormatReader<R> {
+    pub(crate) fn new(input: R) -> Self {
+        FormatReader { input: io::BufReader::new(input), sum: Checksum::new() }
+    }
+
+    pub(crate) fn bytes(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.input.read_exact(buf)?;
+        self.sum.update(buf);
+        Ok(())
+    }
+
+    pub(crate) fn header<T: Pod>(&mut self) -> io::Result<Header> {
+        let mut buf = [0u8; HEADER_LEN];
+        self.bytes(&mut buf)?;
+        Header::decode::<T>(&buf)
+    }
+
+    //whole table up front so a bad file fails before anything big gets allocated
+    pub(crate) fn table(&mut self, header: &Header) -> io::Result<Vec<(u64, usize)>> {
+        let mut table = Vec::with_capacity(header.slots.min(1 << 20));
+        let mut entry = [0u8; SLOT_ENTRY_LEN];
+        let mut offset = 0;
+        for _ in 0..header.slots {
+            self.bytes(&mut entry)?;
+            let (kind, _, len) = decode_slot(&entry, offset)?;
+            offset = offset.checked_add(len).ok_or_else(|| invalid("slot lengths overflow"))?;
+            table.push((kind, len));
+        }
+        if offset != header.len {
+            return Err(invalid("slot table doesn't add up to the length in the header"));
+        }
+        Ok(table)
+    }
+
+    //len comes from the file and isn't trusted before finish, so the vec only grows as fast as the input
+    //actually delivers elements, a made up length runs out of input long before it runs out of memory
+    pub(crate) fn elements<T: Pod>(&mut self, len: usize) -> io::Result<Vec<T>> {
+        let chunk = (ELEMENT_CHUNK_BYTES / mem::size_of::<T>().max(1)).max(1);
+        let mut vals: Vec<T> = Vec::with_capacity(len.min(chunk));
+        while vals.len() < len {
+            let start = vals.len();
+            //zero is a valid value for any Pod
+            vals.resize(start + (len - start).min(chunk), unsafe { mem::zeroed() });
+            self.bytes(as_bytes_mut(&mut vals[start..]))?;
+        }
+        Ok(vals)
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        let mut buf = [0u8; 8];
+        self.input.read_exact(&mut buf)?;
+        if u64::from_le_bytes(buf) != self.sum.get() {
+            return Err(invalid("checksum mismatch"));
+        }
+        Ok(())
+    }
+}
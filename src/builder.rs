@@ -0,0 +1,112 @@
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use crate::fragmented::CompactPolicy;
+use crate::generic::Muted;
+use crate::prefix_index::{FenwickIndex, PrefixIndex};
+use crate::primitive::{PrimitiveMuted, PrimitiveWidth};
+use crate::NightlyGeneric::Muted as UnionMuted;
+
+//one place to set a container up instead of new/new_no_conv/PrimitiveMuted::new(vec, is_32bit)
+//anything left alone is whatever the plain constructors do, settings a container has no use for are ignored
+//(UnionMuted never inlines and width only matters to PrimitiveMuted)
+#[derive(Debug)]
+pub struct MutedBuilder<T> {
+    slots: usize,
+    fragments: usize,
+    prefix: usize,
+    compact: CompactPolicy,
+    inline_below: usize,
+    width: Option<PrimitiveWidth>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for MutedBuilder<T> {
+    fn default() -> Self {
+        MutedBuilder {
+            slots: 0,
+            fragments: 0,
+            prefix: 0,
+            compact: CompactPolicy::Manual,
+            inline_below: 0,
+            width: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> MutedBuilder<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //room for that many slots on top of the initial elements
+    pub fn slot_capacity(mut self, slots: usize) -> Self {
+        self.slots = slots;
+        self
+    }
+
+    //room for that many fragments before the bookkeeping has to grow
+    pub fn fragment_capacity(mut self, fragments: usize) -> Self {
+        self.fragments = fragments;
+        self
+    }
+
+    //room for that many prefix index entries on top of the initial elements
+    pub fn prefix_capacity(mut self, prefix: usize) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    pub fn compact_policy(mut self, policy: CompactPolicy) -> Self {
+        self.compact = policy;
+        self
+    }
+
+    pub fn inline_threshold(mut self, threshold: usize) -> Self {
+        self.inline_below = threshold;
+        self
+    }
+
+    //defaults to Bits32 for 4 byte T and Bits64 for anything else
+    pub fn width(mut self, width: PrimitiveWidth) -> Self {
+        self.width = Some(width);
+        self
+    }
+}
+
+impl<T: Hash + Eq + Debug> MutedBuilder<T> {
+    pub fn build(self, vec: Vec<T>) -> Muted<T> {
+        self.build_with_index(vec)
+    }
+
+    pub fn build_with_index<P: PrefixIndex>(self, vec: Vec<T>) -> Muted<T, P> {
+        let mut muted = Muted::<T, P>::new_with_index(vec);
+        muted.reserve_for(self.slots, self.fragments, self.prefix);
+        muted.set_compact_policy(self.compact);
+        muted.set_inline_threshold(self.inline_below);
+        muted
+    }
+
+    pub fn build_union(self, vec: Vec<T>) -> UnionMuted<T> {
+        let mut muted = UnionMuted::new(vec);
+        muted.reserve_for(self.slots, self.fragments, self.prefix);
+        muted.set_compact_policy(self.compact);
+        muted
+    }
+}
+
+impl<T: Hash + Eq + Debug + Copy + ToString + Display> MutedBuilder<T> {
+    pub fn build_primitive(self, vec: Vec<T>) -> PrimitiveMuted<T> {
+        self.build_primitive_with_index::<FenwickIndex>(vec)
+    }
+
+    pub fn build_primitive_with_index<P: PrefixIndex>(self, vec: Vec<T>) -> PrimitiveMuted<T, P> {
+        let width = self.width.unwrap_or(if size_of::<T>() == 4 { PrimitiveWidth::Bits32 } else { PrimitiveWidth::Bits64 });
+        let mut muted = PrimitiveMuted::<T, P>::new_with_index(vec, width == PrimitiveWidth::Bits32);
+        muted.reserve_for(self.slots, self.prefix);
+        muted.set_compact_policy(self.compact);
+        muted.set_inline_threshold(self.inline_below);
+        muted
+    }
+}
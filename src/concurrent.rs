@@ -0,0 +1,118 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use crate::generic::Muted;
+
+//many producers push fragments into shards without touching each other, publish() moves everything
+//that was pushed so far into a new immutable segment and swaps the snapshot readers see
+//a producer always lands on the same shard so its own fragments keep their order
+pub struct ConcurrentMuted<T: Hash + Eq + Debug> {
+    shards: Vec<Mutex<Vec<Vec<T>>>>,
+    published: RwLock<ConcurrentSnapshot<T>>,
+    publishing: Mutex<()>,
+}
+
+//read only view of everything published up to some point, cheap to clone and to send around
+//segments are never changed after they are published so a snapshot stays valid forever
+pub struct ConcurrentSnapshot<T: Hash + Eq + Debug> {
+    segments: Arc<Vec<(Arc<Muted<T>>, usize)>>,
+}
+
+impl<T: Hash + Eq + Debug> Clone for ConcurrentSnapshot<T> {
+    fn clone(&self) -> Self {
+        ConcurrentSnapshot { segments: self.segments.clone() }
+    }
+}
+
+impl<T: Hash + Eq + Debug> ConcurrentMuted<T> {
+    pub fn new() -> Self {
+        let shards = thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+        Self::with_shards(shards)
+    }
+
+    pub fn with_shards(shards: usize) -> Self {
+        if shards == 0 {
+            panic!("need at least one shard");
+        }
+        ConcurrentMuted {
+            shards: (0..shards).map(|_| Mutex::new(Vec::new())).collect(),
+            published: RwLock::new(ConcurrentSnapshot { segments: Arc::new(Vec::new()) }),
+            publishing: Mutex::new(()),
+        }
+    }
+
+    //only waits on producers that share the same shard, readers are never blocked by this
+    pub fn push_vec(&self, other: Vec<T>) {
+        if other.is_empty() {
+            return;
+        }
+        let shard = &self.shards[Self::shard_of_current(self.shards.len())];
+        shard.lock().unwrap().push(other);
+    }
+
+    //moves every pending fragment into a new segment and makes it visible to snapshot()
+    //readers holding an older snapshot keep seeing exactly what they saw before
+    pub fn publish(&self) -> ConcurrentSnapshot<T> {
+        let _publishing = self.publishing.lock().unwrap();
+        let mut segment = Muted::new(vec![]);
+        for shard in self.shards.iter() {
+            let pending = mem::take(&mut *shard.lock().unwrap());
+            for fragment in pending {
+                segment.push_vec_convert(fragment);
+            }
+        }
+
+        let current = self.snapshot();
+        if segment.is_empty() {
+            return current;
+        }
+        let mut segments: Vec<(Arc<Muted<T>>, usize)> = current.segments.iter().cloned().collect();
+        let end = current.len() + segment.len();
+        segments.push((Arc::new(segment), end));
+        let next = ConcurrentSnapshot { segments: Arc::new(segments) };
+        *self.published.write().unwrap() = next.clone();
+        next
+    }
+
+    pub fn snapshot(&self) -> ConcurrentSnapshot<T> {
+        self.published.read().unwrap().clone()
+    }
+
+    fn shard_of_current(shards: usize) -> usize {
+        let mut hasher = DefaultHasher::new();
+        thread::current().id().hash(&mut hasher);
+        (hasher.finish() % shards as u64) as usize
+    }
+}
+
+impl<T: Hash + Eq + Debug> Default for ConcurrentMuted<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq + Debug> ConcurrentSnapshot<T> {
+    pub fn len(&self) -> usize {
+        self.segments.last().map(|(_, end)| *end).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn read(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+        let seg = self.segments.partition_point(|(_, end)| *end <= index);
+        let start = if seg == 0 { 0 } else { self.segments[seg - 1].1 };
+        self.segments[seg].0.read(index - start)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.segments.iter().flat_map(|(segment, _)| segment.iter())
+    }
+}
@@ -0,0 +1,138 @@
+use std::ops::Range;
+
+//handle to a fragment handed out by push_vec/insert_vec
+//the generation makes sure a handle to a dropped fragment can't reach whatever refills its slot later
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct FragmentId {
+    slot: usize,
+    generation: u32,
+}
+
+impl FragmentId {
+    pub(crate) fn new(slot: usize, generation: u32) -> Self {
+        FragmentId { slot, generation }
+    }
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+    pub(crate) fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+//when a container compacts on its own, the count behind AfterDrops covers drop_vec/drop_fragment/take_fragment
+//and starts over after every compact, manual or not, compacting moves every fragment so ids stop resolving
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum CompactPolicy {
+    #[default]
+    Manual,
+    AfterDrops(usize),
+}
+
+impl CompactPolicy {
+    pub(crate) fn due(&self, drops: usize) -> bool {
+        match self {
+            CompactPolicy::Manual => false,
+            CompactPolicy::AfterDrops(n) => drops >= *n,
+        }
+    }
+}
+
+//what stats() reports, the same for all three containers
+//bytes are counted from capacities and only one level deep, whatever a T owns itself isn't in here
+//elements of a fragment the container doesn't own (shared or borrowed ones) count as zero,
+//an owned fragment a clone still shares is counted by both of them
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MutedStats {
+    pub len: usize,
+    pub slots: usize,
+    pub fragments: usize,
+    pub holes: usize,
+    //all 0 without fragments
+    pub min_fragment_len: usize,
+    pub max_fragment_len: usize,
+    pub avg_fragment_len: f64,
+    //the slot vec
+    pub slot_bytes: usize,
+    //the elements of the fragments
+    pub fragment_bytes: usize,
+    //r_hold, fragment boxes and headers (or the arena slab)
+    pub bookkeeping_bytes: usize,
+    pub prefix_bytes: usize,
+    pub marker_bytes: usize,
+}
+
+impl MutedStats {
+    pub fn heap_bytes(&self) -> usize {
+        self.slot_bytes + self.fragment_bytes + self.bookkeeping_bytes + self.prefix_bytes + self.marker_bytes
+    }
+
+    pub(crate) fn count_fragments(&mut self, lens: impl Iterator<Item = usize>) {
+        let mut total = 0;
+        self.min_fragment_len = usize::MAX;
+        for len in lens {
+            self.fragments += 1;
+            self.min_fragment_len = self.min_fragment_len.min(len);
+            self.max_fragment_len = self.max_fragment_len.max(len);
+            total += len;
+        }
+        if self.fragments == 0 {
+            self.min_fragment_len = 0;
+        } else {
+            self.avg_fragment_len = total as f64 / self.fragments as f64;
+        }
+    }
+}
+
+//either a slot index or a handle, for the calls that take both
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FragmentRef {
+    Slot(usize),
+    Id(FragmentId),
+}
+
+impl From<usize> for FragmentRef {
+    fn from(slot: usize) -> Self {
+        FragmentRef::Slot(slot)
+    }
+}
+
+impl From<FragmentId> for FragmentRef {
+    fn from(id: FragmentId) -> Self {
+        FragmentRef::Id(id)
+    }
+}
+
+//common surface of generic::Muted, PrimitiveMuted and UnionMuted so code can be generic over the backend
+//all element input/output is plain T here, the Data wrappers stay an implementation detail
+pub trait FragmentedVec<T> {
+    type Iter<'a>: Iterator<Item = &'a T> where Self: 'a, T: 'a;
+
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    //None past len(), the same as the snapshots, write also gives None for elements of a read only fragment
+    fn read(&self, index: usize) -> Option<&T>;
+    fn write(&mut self, index: usize, val: T) -> Option<()>;
+    //appends other as a new fragment
+    //a container with an inline threshold (see set_inline_threshold) copies shorter vecs into inline slots instead,
+    //the id handed back for those is fresh but never resolves, fragment_range/drop_fragment/take_fragment give None
+    fn push_vec(&mut self, other: Vec<T>) -> FragmentId;
+    //frees the fragment, leaving a hole in its slot
+    fn drop_fragment(&mut self, id: FragmentId) -> Option<()>;
+    //like drop_fragment but hands the elements back instead of freeing them
+    fn take_fragment(&mut self, at: impl Into<FragmentRef>) -> Option<Vec<T>>;
+    //fills a hole left by drop_fragment
+    fn insert_fragment(&mut self, slot: usize, other: Vec<T>) -> Option<FragmentId>;
+    //the elements of the fragment as plain T whatever the container keeps them in
+    fn fragment(&self, id: FragmentId) -> Option<&[T]>;
+    //None where the fragment is read only (generic::Muted's push_shared/push_slice)
+    fn fragment_mut(&mut self, id: FragmentId) -> Option<&mut [T]>;
+    //logical indices covered by the fragment
+    fn fragment_range(&self, id: FragmentId) -> Option<Range<usize>>;
+    fn iter(&self) -> Self::Iter<'_>;
+    //moves every live element into a single fragment and forgets the holes
+    //fragments the container doesn't own (shared or borrowed ones) keep their own slot
+    fn compact(&mut self);
+}
@@ -1,24 +1,237 @@
-use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
 use std::mem::ManuallyDrop;
+use std::ops::Range;
 use std::ptr;
 use std::ptr::NonNull;
+use std::slice;
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use crate::allocator::{box_in, map_in, vec_in, ABox, AMap, AVec, Allocator, Global};
+use crate::binary::{encode_slot, FormatReader, FormatWriter, Header, Pod, SLOT_FRAGMENT, SLOT_HOLE, SLOT_VAL};
+use crate::fragmented::{CompactPolicy, FragmentId, FragmentRef, FragmentedVec, MutedStats};
+use crate::prefix_index::{FenwickIndex, PrefixIndex};
 use crate::raw_buf::RawBuf;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "serde")]
+use crate::serialize::{Slot, SlotRef, WithFragments};
 
 #[derive(Debug, Eq, Hash, PartialEq)]
 pub enum Data<T: Eq + Hash> {
     Val(T),
-    Rp(Option<NonNull<Vec<Data<T>>>>),
+    Rp(Option<NonNull<Fragment<T>>>),
 }
+
+//where the elements of a fragment live, always plain T, only the top level slots need the Val/Rp split
+//push_vec/push_boxed/push_shared/push_slice all adopt the buffer as it is without touching a single element
+//Owned is behind an Arc so clone() can share it, the first write after that copies just that fragment
+//Shared and Borrowed are read only, writing into them fails the same way writing into a hole does
+//opaque so only the Muted that holds a fragment can make one or share its Arc, see the constructors below
+#[derive(Debug, Eq, PartialEq)]
+pub struct Fragment<T: Eq + Hash>(Repr<T>);
+
 #[derive(Debug, Eq, PartialEq)]
-pub struct Muted<T: Hash + Eq + Debug> {
-    data: Box<Vec<Data<T>>>,
-    r_hold: HashMap<Data<T>, Option<(ManuallyDrop<Box<Vec<Data<T>>>>, usize, usize)>>,
-    prefix_vec: (Vec<usize>, usize),
-    variant_marker: RawBuf,
+enum Repr<T: Eq + Hash> {
+    Owned(Arc<Vec<T>>),
+    //what arena mode stores instead of Owned, no Arc to allocate so clone() and snapshot() copy it instead
+    Unique(Vec<T>),
+    Shared(Arc<[T]>),
+    //only valid as long as the MutedIn<'a> holding it
+    Borrowed(NonNull<[T]>),
+}
+
+//A is where the slot vec, the fragment boxes and r_hold live, see allocator.rs
+//the elements of a fragment stay wherever the Vec/Box/Arc that was pushed put them
+pub struct MutedIn<'a, T: Hash + Eq + Debug, P: PrefixIndex = FenwickIndex, A: Allocator + Clone = Global> {
+    data: ABox<AVec<Data<T>, A>, A>,
+    r_hold: AMap<Data<T>, Option<Held<T, A>>, A>,
+    prefix_vec: (P, usize),
+    variant_marker: RawBuf<A>,
     rc: usize,
+    generation: u32,
+    cursor: Option<CursorCache>,
+    arena: Option<Arena<T, A>>,
+    //vecs shorter than this go into the slots as Val instead of becoming a fragment, 0 never inlines
+    inline_below: usize,
+    //(policy, drops since the last compact)
+    compaction: (CompactPolicy, usize),
+    cloner: Cloner<T>,
+    alloc: A,
+    borrowed: PhantomData<&'a ()>,
+}
+
+//(fragment, slot, len, generation)
+type Held<T, A> = (ManuallyDrop<ABox<Fragment<T>, A>>, usize, usize, u32);
+
+//arena mode, every fragment lives in one slab indexed by where it sits in it instead of its own box plus an r_hold entry
+//the Rp pointers point straight into the slab so every one of them is moved over whenever the slab has to grow
+//freed entries are reused before the slab grows again
+struct Arena<T: Eq + Hash, A: Allocator> {
+    slab: AVec<Option<ArenaEntry<T>>, A>,
+    free: Vec<usize>,
+}
+
+//same as Held minus the box
+#[derive(Debug)]
+struct ArenaEntry<T: Eq + Hash> {
+    fragment: Fragment<T>,
+    slot: usize,
+    len: usize,
+    generation: u32,
+}
+
+impl<T: Eq + Hash, A: Allocator> Arena<T, A> {
+    fn new(alloc: A) -> Self {
+        Arena { slab: vec_in(0, alloc), free: Vec::new() }
+    }
+
+    //the slab moved, every slot still points into the old one
+    fn repoint(&mut self, data: &mut [Data<T>]) {
+        let base = self.slab.as_mut_ptr();
+        for i in 0..self.slab.len() {
+            if let Some(moved) = unsafe { &mut *base.add(i) } {
+                if let Some(Data::Rp(Some(p))) = data.get_mut(moved.slot) {
+                    *p = NonNull::from(&mut moved.fragment);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Eq + Hash + Debug, A: Allocator> Debug for Arena<T, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Arena").field("slab", &&self.slab[..]).field("free", &self.free).finish()
+    }
+}
+
+//Muted owns or shares everything it holds, MutedIn can also borrow fragments for 'a
+pub type Muted<T, P = FenwickIndex, A = Global> = MutedIn<'static, T, P, A>;
+
+//by hand so the allocator doesn't have to be comparable, the cursor and the cloner never were part of it anyway
+impl<'a, T: Hash + Eq + Debug, P: PrefixIndex + Debug, A: Allocator + Clone> Debug for MutedIn<'a, T, P, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MutedIn")
+            .field("data", &self.data)
+            .field("r_hold", &self.r_hold)
+            .field("arena", &self.arena)
+            .field("prefix_vec", &self.prefix_vec)
+            .field("variant_marker", &self.variant_marker)
+            .field("rc", &self.rc)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+impl<'a, T: Hash + Eq + Debug, P: PrefixIndex + PartialEq, A: Allocator + Clone> PartialEq for MutedIn<'a, T, P, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data && self.r_hold == other.r_hold && self.prefix_vec == other.prefix_vec
+            && self.variant_marker == other.variant_marker && self.rc == other.rc && self.generation == other.generation
+    }
+}
+impl<'a, T: Hash + Eq + Debug, P: PrefixIndex + Eq, A: Allocator + Clone> Eq for MutedIn<'a, T, P, A> {}
+
+//the pointers in Rp are owned by the Muted that handed them out, nothing here hands out access to them
+//without going through that Muted so sharing follows T like an Arc<[T]> would
+unsafe impl<T: Eq + Hash + Send + Sync> Send for Data<T> {}
+unsafe impl<T: Eq + Hash + Send + Sync> Sync for Data<T> {}
+unsafe impl<T: Eq + Hash + Send + Sync> Send for Fragment<T> {}
+unsafe impl<T: Eq + Hash + Send + Sync> Sync for Fragment<T> {}
+unsafe impl<'a, T: Hash + Eq + Debug + Send + Sync, P: PrefixIndex + Send, A: Allocator + Clone + Send> Send for MutedIn<'a, T, P, A> {}
+unsafe impl<'a, T: Hash + Eq + Debug + Send + Sync, P: PrefixIndex + Sync, A: Allocator + Clone + Sync> Sync for MutedIn<'a, T, P, A> {}
+
+//(slot, start, end) of the last hit, slot is usize::MAX while there is none
+//a seqlock so reads can still share the Muted across threads without locking, seq is odd while a store is halfway
+//through, a reader that sees it change just searches the index and a store that finds it taken is skipped
+#[derive(Debug)]
+struct CursorCache {
+    seq: AtomicUsize,
+    slot: AtomicUsize,
+    start: AtomicUsize,
+    end: AtomicUsize,
+}
+
+impl Default for CursorCache {
+    fn default() -> Self {
+        CursorCache { seq: AtomicUsize::new(0), slot: AtomicUsize::new(usize::MAX), start: AtomicUsize::new(0), end: AtomicUsize::new(0) }
+    }
+}
+
+impl CursorCache {
+    fn load(&self) -> Option<(usize, usize, usize)> {
+        let seq = self.seq.load(Ordering::Acquire);
+        if seq & 1 == 1 {
+            return None;
+        }
+        let slot = self.slot.load(Ordering::Relaxed);
+        let start = self.start.load(Ordering::Relaxed);
+        let end = self.end.load(Ordering::Relaxed);
+        fence(Ordering::Acquire);
+        if self.seq.load(Ordering::Relaxed) != seq || slot == usize::MAX {
+            return None;
+        }
+        Some((slot, start, end))
+    }
+
+    fn store(&self, slot: usize, start: usize, end: usize) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        if seq & 1 == 1 || self.seq.compare_exchange(seq, seq + 1, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            return;
+        }
+        fence(Ordering::Release);
+        self.slot.store(slot, Ordering::Relaxed);
+        self.start.store(start, Ordering::Relaxed);
+        self.end.store(end, Ordering::Relaxed);
+        self.seq.store(seq + 2, Ordering::Release);
+    }
+
+    fn reset(&mut self) {
+        *self.slot.get_mut() = usize::MAX;
+    }
+}
+
+//just a cache, never part of what a Muted is
+impl PartialEq for CursorCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+impl Eq for CursorCache {}
+
+//how to copy a fragment that is shared with a clone or a snapshot, only clone() and snapshot() can share one
+//and they need T: Clone anyway so that's where this gets set, write doesn't have to ask for Clone itself
+struct Cloner<T>(OnceLock<ClonerFn<T>>);
+
+type ClonerFn<T> = fn(&[T]) -> Vec<T>;
+
+impl<T> Cloner<T> {
+    fn get(&self) -> Option<ClonerFn<T>> {
+        self.0.get().copied()
+    }
+}
+
+impl<T> Default for Cloner<T> {
+    fn default() -> Self {
+        Cloner(OnceLock::new())
+    }
+}
+
+impl<T> Debug for Cloner<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cloner({})", self.0.get().is_some())
+    }
+}
+
+impl<T> PartialEq for Cloner<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
 }
+impl<T> Eq for Cloner<T> {}
 
 #[macro_export]
 macro_rules! muted {
@@ -36,11 +249,128 @@ macro_rules! muted {
     }}
 }
 
+impl<T: Eq + Hash> Fragment<T> {
+    pub(crate) fn owned(vec: Vec<T>) -> Self {
+        Fragment(Repr::Owned(Arc::new(vec)))
+    }
+
+    pub(crate) fn unique(vec: Vec<T>) -> Self {
+        Fragment(Repr::Unique(vec))
+    }
+
+    pub(crate) fn shared(shared: Arc<[T]>) -> Self {
+        Fragment(Repr::Shared(shared))
+    }
+
+    /// # Safety
+    ///
+    /// `borrowed` has to stay alive and unchanged for as long as the fragment, and every fragment shared from it,
+    /// is around. `MutedIn<'a>` makes sure of that by only taking `&'a [T]` and never letting a fragment outlive `'a`.
+    pub(crate) unsafe fn borrowed(borrowed: &[T]) -> Self {
+        Fragment(Repr::Borrowed(NonNull::from(borrowed)))
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Repr::Owned(vec) => vec.len(),
+            Repr::Unique(vec) => vec.len(),
+            Repr::Shared(shared) => shared.len(),
+            Repr::Borrowed(borrowed) => borrowed.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-impl<T: Eq + Hash + Debug + Clone> Iterator for Muted<T>{
+    pub fn is_read_only(&self) -> bool {
+        matches!(self.0, Repr::Shared(_) | Repr::Borrowed(_))
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        match &self.0 {
+            Repr::Owned(vec) => vec,
+            Repr::Unique(vec) => vec,
+            Repr::Shared(shared) => shared,
+            Repr::Borrowed(borrowed) => unsafe { borrowed.as_ref() },
+        }
+    }
+
+    //None for read only fragments and for ones still shared with a clone
+    pub fn as_mut_slice(&mut self) -> Option<&mut [T]> {
+        match &mut self.0 {
+            Repr::Owned(vec) => Some(Arc::get_mut(vec)?),
+            Repr::Unique(vec) => Some(vec),
+            _ => None,
+        }
+    }
+
+    //an Owned fragment shared with a clone is copied first, like Arc::make_mut
+    fn make_mut(&mut self, cloner: Option<ClonerFn<T>>) -> Option<&mut [T]> {
+        if let Repr::Owned(vec) = &mut self.0 {
+            if Arc::get_mut(vec).is_none() {
+                let cloner = cloner.expect("fragment shared without a clone");
+                *vec = Arc::new(cloner(vec));
+            }
+        }
+        self.as_mut_slice()
+    }
+
+    //new handle onto the same elements, O(1) for every kind but Unique which has nothing to share and gets copied
+    fn share(&self, cloner: ClonerFn<T>) -> Self {
+        Fragment(match &self.0 {
+            Repr::Owned(vec) => Repr::Owned(Arc::clone(vec)),
+            Repr::Unique(vec) => Repr::Unique(cloner(vec)),
+            Repr::Shared(shared) => Repr::Shared(Arc::clone(shared)),
+            Repr::Borrowed(borrowed) => Repr::Borrowed(*borrowed),
+        })
+    }
+
+    pub fn get(&self, offset: usize) -> Option<&T> {
+        self.as_slice().get(offset)
+    }
+
+    fn get_mut(&mut self, offset: usize, cloner: Option<ClonerFn<T>>) -> Option<&mut T> {
+        self.make_mut(cloner)?.get_mut(offset)
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    //elements this fragment owns, for stats()
+    fn owned_bytes(&self) -> usize {
+        match &self.0 {
+            Repr::Owned(vec) => vec.capacity() * size_of::<T>(),
+            Repr::Unique(vec) => vec.capacity() * size_of::<T>(),
+            _ => 0,
+        }
+    }
+
+    //the Arc around an Owned one, for stats()
+    fn header_bytes(&self) -> usize {
+        match &self.0 {
+            Repr::Owned(_) => size_of::<Vec<T>>() + 2 * size_of::<usize>(),
+            _ => 0,
+        }
+    }
+
+    //owned elements back out, copied if a clone still shares them, read only fragments can't give theirs up
+    fn into_vec(self, cloner: Option<ClonerFn<T>>) -> Result<Vec<T>, Self> {
+        match self.0 {
+            Repr::Owned(vec) => Ok(Arc::try_unwrap(vec).unwrap_or_else(|vec| {
+                cloner.expect("fragment shared without a clone")(&vec)
+            })),
+            Repr::Unique(vec) => Ok(vec),
+            other => Err(Fragment(other)),
+        }
+    }
+}
+
+impl<'a, T: Eq + Hash + Debug + Clone, P: PrefixIndex, A: Allocator + Clone> Iterator for MutedIn<'a, T, P, A>{
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.prefix_vec.1 >= self.data.len() {
+        if self.prefix_vec.1 >= self.len() {
             return None;
         }
 
@@ -51,9 +381,32 @@ impl<T: Eq + Hash + Debug + Clone> Iterator for Muted<T>{
     }
 }
 
-impl<T: Eq + Hash + Debug> Drop for Muted<T>{
+pub struct Iter<'a, T: Eq + Hash> {
+    slots: slice::Iter<'a, Data<T>>,
+    inner: slice::Iter<'a, T>,
+}
+
+impl<'a, T: Eq + Hash> Iterator for Iter<'a, T>{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(v) = self.inner.next() {
+                return Some(v);
+            }
+            match self.slots.next()? {
+                Data::Val(v) => return Some(v),
+                Data::Rp(Some(p)) => self.inner = unsafe { p.as_ref() }.iter(),
+                Data::Rp(None) => (),
+            }
+        }
+    }
+}
+
+impl<'a, T: Eq + Hash + Debug, P: PrefixIndex, A: Allocator + Clone> Drop for MutedIn<'a, T, P, A>{
     fn drop(&mut self) {
-        let mut to_drop: Vec<(NonNull<Vec<Data<T>>>, usize)> = Vec::new();
+        //drop_vec below must not kick off a compact halfway through
+        self.compaction.0 = CompactPolicy::Manual;
+        let mut to_drop: Vec<(NonNull<Fragment<T>>, usize)> = Vec::new();
         for (idx, item) in self.data.iter_mut().enumerate() {
             if let Data::Rp(opt_ptr) = item {
                 if let Some(ptr) = opt_ptr.take() {
@@ -73,7 +426,43 @@ impl<T: Eq + Hash + Debug> Drop for Muted<T>{
     }
 }
 
-impl<T: Eq + Hash + Debug> Display for Muted<T>{
+//O(slots), fragments are shared with the clone and only copied once one side writes into them
+impl<'a, T: Eq + Hash + Debug + Clone, P: PrefixIndex + Clone, A: Allocator + Clone> Clone for MutedIn<'a, T, P, A>{
+    fn clone(&self) -> Self {
+        let cloner = self.share_fragments();
+        let mut copy = MutedIn{
+            data: box_in(vec_in(self.data.len(), self.alloc.clone()), self.alloc.clone()),
+            r_hold: map_in(self.r_hold.len(), self.alloc.clone()),
+            prefix_vec: (self.prefix_vec.0.clone(), self.prefix_vec.1),
+            variant_marker: self.variant_marker.clone(),
+            rc: self.rc,
+            generation: self.generation,
+            cursor: self.cursor.as_ref().map(|_| CursorCache::default()),
+            arena: self.arena.as_ref().map(|_| Arena::new(self.alloc.clone())),
+            inline_below: self.inline_below,
+            compaction: self.compaction,
+            cloner: Cloner::default(),
+            alloc: self.alloc.clone(),
+            borrowed: PhantomData,
+        };
+        for item in self.data.iter() {
+            let slot = match item {
+                Data::Val(v) => Data::Val(v.clone()),
+                Data::Rp(None) => Data::Rp(None),
+                Data::Rp(Some(p)) => {
+                    let (_, _, generation) = self.entry(*p).expect("fragment missing from r_hold");
+                    let fragment = unsafe { p.as_ref() }.share(cloner);
+                    Data::Rp(Some(copy.hold(fragment, copy.data.len(), generation)))
+                }
+            };
+            copy.data.push(slot);
+        }
+        let _ = copy.cloner.0.set(cloner);
+        copy
+    }
+}
+
+impl<'a, T: Eq + Hash + Debug, P: PrefixIndex, A: Allocator + Clone> Display for MutedIn<'a, T, P, A>{
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if f.alternate() {
             write!(f, "{:?}", self.data).expect("data not available");
@@ -86,14 +475,8 @@ impl<T: Eq + Hash + Debug> Display for Muted<T>{
                     Data::Val(v) => display_vec.push(v),
                     Data::Rp(p1) => if let Some(p) = p1{
                         unsafe {
-                            let vec: &Vec<Data<T>> = p.as_ref();
-                            for i in vec.iter(){
-                                match i {
-                                    Data::Val(v) => display_vec.push(v),
-                                    _ => unreachable!("please no nested pointers (may be added later)"),
-                                }
-
-                            }
+                            let fragment: &Fragment<T> = p.as_ref();
+                            display_vec.extend(fragment.iter());
                         }
                     }
                 }
@@ -105,28 +488,14 @@ impl<T: Eq + Hash + Debug> Display for Muted<T>{
     }
 }
 
-impl<T: Hash + Eq + Debug> Muted<T>{
+impl<'a, T: Hash + Eq + Debug> MutedIn<'a, T>{
     pub fn new(vec: Vec<T>) -> Self{
-        let len = vec.len();
-        return Muted{
-            data: Box::new(Self::muted_from(vec)),
-            r_hold: HashMap::new(),
-            prefix_vec: ((1..=len).collect(), len),
-            variant_marker: RawBuf::new(1),
-            rc: 0
-        };
+        Self::new_with_index(vec)
     }
     pub fn new_no_conv(vec: Vec<Data<T>>) -> Self{
-        let len = vec.len();
-        return Muted{
-            data: Box::new(vec),
-            r_hold: HashMap::new(),
-            prefix_vec: ((1..=len).collect(), len),
-            variant_marker: RawBuf::new(1),
-            rc: 0
-        };
+        Self::from_slots(vec)
     }
-    
+
     pub fn muted_from(other: Vec<T>) -> Vec<Data<T>>{
         let len = other.len();
         let cap = other.capacity();
@@ -137,33 +506,363 @@ impl<T: Hash + Eq + Debug> Muted<T>{
             unsafe { ptr::write(dst.add(i), Data::Val(item)); }
         }
         unsafe { new.set_len(len); }
-        return new;
+        new
+    }
+    //reverse of muted_from
+    pub fn muted_into(other: Vec<Data<T>>) -> Vec<T>{
+        other.into_iter().map(|item| match item {
+            Data::Val(v) => v,
+            Data::Rp(_) => unreachable!("please no nested pointers (may be added later)"),
+        }).collect()
+    }
+}
+
+impl<'a, T: Hash + Eq + Debug, P: PrefixIndex> MutedIn<'a, T, P>{
+    //same as new but with a prefix index other than the default fenwick tree
+    pub fn new_with_index(vec: Vec<T>) -> Self{
+        Self::from_slots(MutedIn::<T>::muted_from(vec))
     }
-    pub fn push_vec_convert(&mut self, other: Vec<T>){
-        let other = Self::muted_from(other);
-        self.push_vec(other);
+    fn from_slots(vec: Vec<Data<T>>) -> Self{
+        Self::from_slots_in(vec, Global)
     }
-    pub fn push_vec(&mut self, other: Vec<Data<T>>) {
+}
+
+impl<'a, T: Hash + Eq + Debug, P: PrefixIndex, A: Allocator + Clone> MutedIn<'a, T, P, A>{
+    //like new_with_index but the slots, fragment boxes and r_hold come from alloc
+    pub fn new_in(vec: Vec<T>, alloc: A) -> Self{
+        let mut slots = vec_in(vec.len(), alloc.clone());
+        slots.extend(vec.into_iter().map(Data::Val));
+        Self::from_slots_in(slots, alloc)
+    }
+    fn from_slots_in(vec: AVec<Data<T>, A>, alloc: A) -> Self{
+        let len = vec.len();
+        MutedIn{
+            data: box_in(vec, alloc.clone()),
+            r_hold: map_in(0, alloc.clone()),
+            prefix_vec: (P::from_lens(vec![1; len]), 0),
+            variant_marker: RawBuf::new_in(1, alloc.clone()),
+            rc: 0,
+            generation: 0,
+            cursor: None,
+            arena: None,
+            inline_below: 0,
+            compaction: (CompactPolicy::Manual, 0),
+            cloner: Cloner::default(),
+            alloc,
+            borrowed: PhantomData,
+        }
+    }
+    pub fn allocator(&self) -> &A{
+        &self.alloc
+    }
+
+    //fragments hold plain T now so there is nothing left to convert, kept so old callers still build
+    pub fn push_vec_convert(&mut self, other: Vec<T>) -> FragmentId{
+        self.push_vec(other)
+    }
+    //O(1), the vec becomes the fragment as it is, unless it is short enough to be inlined (see set_inline_threshold)
+    //an inlined vec is no fragment, the id that comes back for it never resolves to anything
+    pub fn push_vec(&mut self, other: Vec<T>) -> FragmentId{
+        if other.len() < self.inline_below {
+            let id = self.next_id(self.data.len());
+            for val in other {
+                self.push_inline(val);
+            }
+            return id;
+        }
+        let fragment = self.owned(other);
+        self.push_fragment(fragment)
+    }
+    //takes the allocation over as it is, O(1) no matter the length
+    pub fn push_boxed(&mut self, other: Box<[T]>) -> FragmentId{
+        self.push_vec(other.into_vec())
+    }
+    //shares the buffer with whoever else holds the Arc, the fragment is read only
+    pub fn push_shared(&mut self, other: Arc<[T]>) -> FragmentId{
+        self.push_fragment(Fragment::shared(other))
+    }
+    //borrows the slice for 'a, the fragment is read only
+    pub fn push_slice(&mut self, other: &'a [T]) -> FragmentId{
+        //other lives for 'a and so does everything this MutedIn hands its fragments to
+        self.push_fragment(unsafe { Fragment::borrowed(other) })
+    }
+    fn push_fragment(&mut self, other: Fragment<T>) -> FragmentId{
         let len = other.len();
-        let mut other = ManuallyDrop::new(Box::new(other));
-        let ptr: &mut Vec<Data<T>> = &mut **other;
-        let maybe_ptr = NonNull::new(ptr);
-        self.r_hold.insert(Data::Rp(maybe_ptr), Some((other, self.data.len(), len)));
-        self.data.push(Data::Rp(maybe_ptr));
+        let id = self.next_id(self.data.len());
+        let ptr = self.hold(other, id.slot(), id.generation());
+        self.data.push(Data::Rp(Some(ptr)));
         self.rc += 1;
 
-        let last = self.prefix_vec.0.last().cloned().unwrap_or(0);
-        self.prefix_vec.0.push(last + len);
-        self.prefix_vec.1 = self.prefix_vec.0.len();
+        self.prefix_vec.0.push(len);
+        id
+    }
+    //appends to the last fragment if it can grow without being copied, otherwise val starts a new one
+    //(which push_vec inlines as a Val if the inline threshold says so)
+    //O(1) amortized either way, both only set or push the last prefix slot
+    pub fn push(&mut self, val: T){
+        if let Some(ptr) = self.data.len().checked_sub(1).and_then(|last| self.slot_ptr(last)) {
+            let growable = match unsafe { &mut (*ptr.as_ptr()).0 } {
+                Repr::Owned(vec) => Arc::get_mut(vec),
+                Repr::Unique(vec) => Some(vec),
+                _ => None,
+            };
+            if let Some(vec) = growable {
+                vec.push(val);
+                let len = vec.len();
+                self.resize_held(ptr, len);
+                self.prefix_vec.0.set(self.data.len() - 1, len);
+                //the cursor may still hold the old end of this slot
+                self.reset_cursor();
+                return;
+            }
+        }
+        self.push_vec(vec![val]);
     }
     pub fn is_empty(&self) -> bool{
-        self.data.is_empty()
+        self.len() == 0
     }
+    //only live elements, holes left by drop_vec count as zero
     pub fn len(&self) -> usize {
-        *self.prefix_vec.0.last().unwrap()
+        self.prefix_vec.0.total()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter{
+            slots: self.data.iter(),
+            inner: [].iter(),
+        }
+    }
+
+    //owned elements are merged into one fragment, read only fragments stay as they are since they can't be moved
+    pub fn compact(&mut self){
+        let slots = std::mem::replace(&mut *self.data, vec_in(0, self.alloc.clone()));
+        let mut held: Vec<Fragment<T>> = Vec::new();
+        let mut merged: Vec<T> = Vec::with_capacity(self.len());
+        for slot in slots {
+            match slot {
+                Data::Val(v) => merged.push(v),
+                Data::Rp(Some(ptr)) => {
+                    if let Some(fragment) = self.release(ptr) {
+                        match fragment.into_vec(self.cloner.get()) {
+                            Ok(vec) => merged.extend(vec),
+                            Err(read_only) => {
+                                if !merged.is_empty() {
+                                    held.push(self.owned(std::mem::take(&mut merged)));
+                                }
+                                held.push(read_only);
+                            }
+                        }
+                    }
+                }
+                Data::Rp(None) => (),
+            }
+        }
+        if !merged.is_empty() {
+            held.push(self.owned(merged));
+        }
+        if let Some(arena) = &mut self.arena {
+            arena.slab.clear();
+            arena.free.clear();
+        }
+        self.compaction.1 = 0;
+        self.rc = 0;
+        self.prefix_vec.0.clear();
+        self.reset_cursor();
+        for fragment in held {
+            self.push_fragment(fragment);
+        }
+    }
+
+    pub fn drop_fragment(&mut self, id: FragmentId) -> Option<()>{
+        self.held(id)?;
+        self.drop_vec(Some(id.slot()), None)
+    }
+
+    //cheap move out to match the cheap copy in, leaves a hole just like drop_vec
+    //read only fragments don't own their elements so they stay where they are and this returns None
+    pub fn take_fragment(&mut self, at: impl Into<FragmentRef>) -> Option<Vec<T>>{
+        let slot = match at.into() {
+            FragmentRef::Slot(slot) => slot,
+            FragmentRef::Id(id) => self.held(id)?.1,
+        };
+        let ptr = self.slot_ptr(slot)?;
+        if unsafe { ptr.as_ref() }.is_read_only() {
+            return None;
+        }
+        let fragment = self.release(ptr)?;
+        self.data[slot] = Data::Rp(None);
+        self.prefix_vec.0.set(slot, 0);
+        self.reset_cursor();
+        self.rc -= 1;
+        let taken = fragment.into_vec(self.cloner.get()).ok();
+        self.dropped();
+        taken
+    }
+
+    pub fn fragment(&self, id: FragmentId) -> Option<&[T]>{
+        let (ptr, _, _) = self.held(id)?;
+        Some(unsafe { &*ptr.as_ptr() }.as_slice())
+    }
+
+    //None for read only fragments
+    pub fn fragment_mut(&mut self, id: FragmentId) -> Option<&mut [T]>{
+        let (ptr, _, _) = self.held(id)?;
+        unsafe { (*ptr.as_ptr()).make_mut(self.cloner.get()) }
+    }
+
+    pub fn fragment_range(&self, id: FragmentId) -> Option<Range<usize>>{
+        let (_, slot, len) = self.held(id)?;
+        let start = self.prefix_vec.0.prefix(slot);
+        Some(start..start + len)
+    }
+
+    //moves every fragment into one slab (or back into boxes), see Arena, O(fragments)
+    //vecs pushed while it is on skip the Arc too so clone() and snapshot() copy those instead of sharing them
+    pub fn set_arena(&mut self, enabled: bool){
+        if enabled == self.arena.is_some() {
+            return;
+        }
+        let mut moved = Vec::with_capacity(self.rc);
+        for slot in 0..self.data.len() {
+            if let Some(ptr) = self.slot_ptr(slot) {
+                let (_, _, generation) = self.entry(ptr).expect("fragment missing from r_hold");
+                let fragment = self.release(ptr).expect("fragment missing from r_hold");
+                moved.push((slot, fragment, generation));
+            }
+        }
+        self.arena = if enabled { Some(Arena::new(self.alloc.clone())) } else { None };
+        for (slot, fragment, generation) in moved {
+            let ptr = self.hold(fragment, slot, generation);
+            self.data[slot] = Data::Rp(Some(ptr));
+        }
+    }
+
+    pub fn is_arena(&self) -> bool{
+        self.arena.is_some()
+    }
+
+    //push_vec copies vecs shorter than threshold into the slots one Val each, the way new() stores its elements
+    //saves the box, the r_hold entry and the Arc for tiny vecs, the id it returns for those refers to no fragment
+    //so fragment/fragment_range/drop_fragment give None for it, fragments that are already there stay as they are
+    pub fn set_inline_threshold(&mut self, threshold: usize){
+        self.inline_below = threshold;
+    }
+
+    pub fn with_inline_threshold(mut self, threshold: usize) -> Self{
+        self.set_inline_threshold(threshold);
+        self
+    }
+
+    pub fn inline_threshold(&self) -> usize{
+        self.inline_below
+    }
+
+    fn owned(&self, vec: Vec<T>) -> Fragment<T>{
+        if self.arena.is_some() { Fragment::unique(vec) } else { Fragment::owned(vec) }
+    }
+
+    //keeps the fragment for slot, boxed and in r_hold or in the arena, the pointer is what goes into Rp
+    fn hold(&mut self, fragment: Fragment<T>, slot: usize, generation: u32) -> NonNull<Fragment<T>>{
+        let len = fragment.len();
+        let arena = match &mut self.arena {
+            Some(arena) => arena,
+            None => {
+                let mut other = ManuallyDrop::new(box_in(fragment, self.alloc.clone()));
+                let ptr = NonNull::from(&mut **other);
+                self.r_hold.insert(Data::Rp(Some(ptr)), Some((other, slot, len, generation)));
+                return ptr;
+            }
+        };
+        let entry = Some(ArenaEntry { fragment, slot, len, generation });
+        let idx = match arena.free.pop() {
+            Some(idx) => {
+                //the old entry is None, nothing to drop
+                unsafe { arena.slab.as_mut_ptr().add(idx).write(entry); }
+                idx
+            }
+            None => {
+                let before = arena.slab.as_ptr();
+                arena.slab.push(entry);
+                if arena.slab.as_ptr() != before {
+                    arena.repoint(&mut self.data);
+                }
+                arena.slab.len() - 1
+            }
+        };
+        match unsafe { &mut *arena.slab.as_mut_ptr().add(idx) } {
+            Some(entry) => NonNull::from(&mut entry.fragment),
+            None => unreachable!(),
+        }
+    }
+
+    //reverse of hold, None if ptr isn't one of ours
+    fn release(&mut self, ptr: NonNull<Fragment<T>>) -> Option<Fragment<T>>{
+        let arena = match &mut self.arena {
+            Some(arena) => arena,
+            None => {
+                let held = self.r_hold.remove(&Data::Rp(Some(ptr)))??;
+                let boxed: ABox<Fragment<T>, A> = ManuallyDrop::into_inner(held.0);
+                return Some(*boxed);
+            }
+        };
+        let idx = Self::arena_index(arena, ptr)?;
+        let entry = unsafe { (*arena.slab.as_mut_ptr().add(idx)).take()? };
+        arena.free.push(idx);
+        Some(entry.fragment)
+    }
+
+    //(slot, len, generation) of the fragment behind ptr
+    fn entry(&self, ptr: NonNull<Fragment<T>>) -> Option<(usize, usize, u32)>{
+        let arena = match &self.arena {
+            Some(arena) => arena,
+            None => return match self.r_hold.get(&Data::Rp(Some(ptr))) {
+                Some(Some(held)) => Some((held.1, held.2, held.3)),
+                _ => None,
+            },
+        };
+        let idx = Self::arena_index(arena, ptr)?;
+        let entry = unsafe { (*arena.slab.as_ptr().add(idx)).as_ref()? };
+        Some((entry.slot, entry.len, entry.generation))
+    }
+
+    fn arena_index(arena: &Arena<T, A>, ptr: NonNull<Fragment<T>>) -> Option<usize>{
+        let offset = (ptr.as_ptr() as usize).checked_sub(arena.slab.as_ptr() as usize)?;
+        let idx = offset / std::mem::size_of::<Option<ArenaEntry<T>>>();
+        if idx < arena.slab.len() { Some(idx) } else { None }
+    }
+
+    fn slot_ptr(&self, slot: usize) -> Option<NonNull<Fragment<T>>>{
+        match self.data.get(slot) {
+            Some(Data::Rp(p)) => *p,
+            _ => None,
+        }
+    }
+
+    fn push_inline(&mut self, val: T){
+        self.data.push(Data::Val(val));
+        self.prefix_vec.0.push(1);
+    }
+
+    fn push_hole(&mut self){
+        self.data.push(Data::Rp(None));
+        self.prefix_vec.0.push(0);
+    }
+
+    //(fragment, slot, len) behind id, if id still refers to the fragment it was issued for
+    fn held(&self, id: FragmentId) -> Option<(NonNull<Fragment<T>>, usize, usize)>{
+        let ptr = self.slot_ptr(id.slot())?;
+        match self.entry(ptr)? {
+            (slot, len, generation) if generation == id.generation() => Some((ptr, slot, len)),
+            _ => None,
+        }
     }
 
-    pub fn drop_vec(&mut self, index: Option<usize>, maybe_ptr: Option<NonNull<Vec<Data<T>>>>) -> Option<()>{
+    fn next_id(&mut self, slot: usize) -> FragmentId{
+        self.generation = self.generation.wrapping_add(1);
+        FragmentId::new(slot, self.generation)
+    }
+
+    pub fn drop_vec(&mut self, index: Option<usize>, maybe_ptr: Option<NonNull<Fragment<T>>>) -> Option<()>{
         match (index, maybe_ptr) {
             (Some(_),Some(_)) => {
                 panic!("choose either index or direct pointer");
@@ -173,137 +872,703 @@ impl<T: Hash + Eq + Debug> Muted<T>{
             }
             _ => (),
         }
-        let mut real_index = None;
-        if let Some(key_index) = self.r_hold.get_key_value(&Data::Rp(maybe_ptr)){
-            if let Some(x) = key_index.1{
-                real_index = Some(x.1);
-            }
+        let (slot, ptr) = match (index, maybe_ptr) {
+            (Some(slot), _) => (slot, self.slot_ptr(slot)?),
+            (_, Some(ptr)) => (self.entry(ptr)?.0, ptr),
+            _ => unreachable!(),
+        };
+        std::mem::drop(self.release(ptr)?);
+        self.data[slot] = Data::Rp(None);
+        self.prefix_vec.0.set(slot, 0);
+        self.reset_cursor();
+        self.rc -= 1;
+        self.dropped();
+        Some(())
+    }
+
+    //see CompactPolicy, Manual (the default) never compacts on its own
+    pub fn set_compact_policy(&mut self, policy: CompactPolicy){
+        self.compaction = (policy, 0);
+    }
+
+    fn dropped(&mut self){
+        self.compaction.1 += 1;
+        if self.compaction.0.due(self.compaction.1) {
+            self.compact();
         }
-        if let Some(u_index) = index{
-            real_index = Some(u_index);
+    }
+
+    //what MutedBuilder hands over, room for more slots, fragments and prefix entries
+    pub(crate) fn reserve_for(&mut self, slots: usize, fragments: usize, prefix: usize){
+        self.data.reserve(slots);
+        match &mut self.arena {
+            Some(arena) => arena.slab.reserve(fragments),
+            None => self.r_hold.reserve(fragments),
         }
+        self.prefix_vec.0.reserve(prefix);
+    }
 
-        if let Some(held) = self.r_hold.remove(&Data::Rp(maybe_ptr)) {
-            self.data[real_index.unwrap()] = Data::Rp(None);
-            let boxed: Box<Vec<Data<T>>> = ManuallyDrop::into_inner(match held {
-                Some(x) => x.0,
-                None => unreachable!(),
-            });
-            std::mem::drop(boxed);
-            self.rc -= 1;
-            return Some(());
+    //room for additional more slots and prefix entries, fragments bring their own elements
+    pub fn reserve(&mut self, additional: usize){
+        self.reserve_for(additional, 0, additional);
+    }
+
+    //gives back what slots, bookkeeping and owned fragments hold on top of what they use
+    //a fragment still shared with a clone is left alone, shrinking it would mean copying it
+    pub fn shrink_to_fit(&mut self){
+        for slot in self.data.iter() {
+            if let Data::Rp(Some(p)) = slot {
+                match unsafe { &mut (*p.as_ptr()).0 } {
+                    Repr::Owned(vec) => if let Some(vec) = Arc::get_mut(vec) {
+                        vec.shrink_to_fit();
+                    },
+                    Repr::Unique(vec) => vec.shrink_to_fit(),
+                    _ => (),
+                }
+            }
         }
-        if real_index.is_some() && !self.data.is_empty(){
-            let ptr = match &self.data[real_index.unwrap()] {
-                Data::Rp(p) => p,
-                Data::Val(_v) => return None,
-            };
-            if let Some(held) = self.r_hold.remove(&Data::Rp(*ptr)) {
-                let boxed: Box<Vec<Data<T>>> = ManuallyDrop::into_inner(match held {
-                    Some(x) => x.0,
-                    None => unreachable!(),
-                });
-                
-                std::mem::drop(boxed);
-                self.rc -= 1;
-            }else {
-                return None;
+        self.data.shrink_to_fit();
+        self.r_hold.shrink_to_fit();
+        if let Some(arena) = &mut self.arena {
+            arena.free.shrink_to_fit();
+            let before = arena.slab.as_ptr();
+            arena.slab.shrink_to_fit();
+            if arena.slab.as_ptr() != before {
+                arena.repoint(&mut self.data);
+            }
+        }
+        self.prefix_vec.0.shrink_to_fit();
+        self.variant_marker.data.shrink_to_fit();
+    }
+
+    //drops every element and fragment, the capacity stays
+    pub fn clear(&mut self){
+        for slot in 0..self.data.len() {
+            if let Some(ptr) = self.slot_ptr(slot) {
+                std::mem::drop(self.release(ptr));
             }
-            self.data[real_index.unwrap()] = Data::Rp(None);
-            return Some(());
-        }else {
-            return None;
         }
+        self.data.clear();
+        if let Some(arena) = &mut self.arena {
+            arena.slab.clear();
+            arena.free.clear();
+        }
+        self.prefix_vec.0.clear();
+        self.prefix_vec.1 = 0;
+        self.rc = 0;
+        self.reset_cursor();
+    }
+
+    //keeps the len r_hold (or the arena) has for a fragment in step after it changed size
+    fn resize_held(&mut self, ptr: NonNull<Fragment<T>>, len: usize){
+        match &mut self.arena {
+            Some(arena) => if let Some(idx) = Self::arena_index(arena, ptr) {
+                if let Some(entry) = unsafe { &mut *arena.slab.as_mut_ptr().add(idx) } {
+                    entry.len = len;
+                    //the &mut above covers the fragment too, the pointer in the slot has to come from it now
+                    self.data[entry.slot] = Data::Rp(Some(NonNull::from(&mut entry.fragment)));
+                }
+            },
+            None => if let Some(Some(held)) = self.r_hold.get_mut(&Data::Rp(Some(ptr))) {
+                held.2 = len;
+            },
+        }
+    }
+
+    pub fn fragment_count(&self) -> usize{
+        self.rc
+    }
+
+    pub fn hole_count(&self) -> usize{
+        self.data.iter().filter(|slot| matches!(slot, Data::Rp(None))).count()
+    }
+
+    //in slots, a fragment takes one however long it is
+    pub fn capacity(&self) -> usize{
+        self.data.capacity()
+    }
+
+    pub fn heap_bytes(&self) -> usize{
+        self.stats().heap_bytes()
+    }
+
+    //O(slots)
+    pub fn stats(&self) -> MutedStats{
+        let fragments = || self.data.iter().filter_map(|slot| match slot {
+            Data::Rp(Some(p)) => Some(unsafe { p.as_ref() }),
+            _ => None,
+        });
+        let mut stats = MutedStats { len: self.len(), slots: self.data.len(), holes: self.hole_count(), ..MutedStats::default() };
+        stats.count_fragments(fragments().map(Fragment::len));
+        stats.slot_bytes = self.data.capacity() * size_of::<Data<T>>();
+        stats.fragment_bytes = fragments().map(Fragment::owned_bytes).sum();
+        let headers: usize = fragments().map(Fragment::header_bytes).sum();
+        stats.bookkeeping_bytes = headers + match &self.arena {
+            Some(arena) => arena.slab.capacity() * size_of::<Option<ArenaEntry<T>>>() + arena.free.capacity() * size_of::<usize>(),
+            None => self.r_hold.capacity() * (size_of::<Data<T>>() + size_of::<Option<Held<T, A>>>() + 1)
+                + self.rc * size_of::<Fragment<T>>(),
+        };
+        stats.prefix_bytes = self.prefix_vec.0.heap_bytes();
+        stats.marker_bytes = self.variant_marker.data.capacity();
+        stats
     }
 
-    pub unsafe fn insert_vec_unchecked(&mut self, index: usize, other: Vec<T>) -> Option<()>{
-        self.insert_vec_inner(index, other, true)
+    //insert_vec without the bounds check
+    /// # Safety
+    ///
+    /// `index` has to be below the number of slots. Past the end indexing the slots panics,
+    /// later versions may index them without checking at all.
+    pub unsafe fn insert_vec_unchecked(&mut self, index: usize, other: Vec<T>) -> Option<FragmentId>{
+        self.insert_vec_inner(index, other)
     }
 
-    pub fn insert_vec(&mut self, index: usize, other: Vec<T>) -> Option<()>{
+    pub fn insert_vec(&mut self, index: usize, other: Vec<T>) -> Option<FragmentId>{
         if index >= self.data.len() { return None; }
-        self.insert_vec_inner(index, other, false)
+        self.insert_vec_inner(index, other)
     }
 
-    fn insert_vec_inner(&mut self, index: usize, vec: Vec<T>, skip_calibration: bool) -> Option<()>{
+    fn insert_vec_inner(&mut self, index: usize, vec: Vec<T>) -> Option<FragmentId>{
         let len = vec.len();
-        let wrapped: Vec<Data<T>> = vec.into_iter().map(Data::Val).collect();
-        let mut other = ManuallyDrop::new(Box::new(wrapped));
-        let ptr= NonNull::new(&mut **other);
-        if self.data[index] == Data::Rp(None) {
-            self.r_hold.insert(Data::Rp(ptr), Some((other, index, len)));
-            self.data[index] = Data::Rp(ptr);
-        }else {
-            {
-                ManuallyDrop::into_inner(other);
-            }
+        if self.data[index] != Data::Rp(None) {
             return None;
         }
+        let id = self.next_id(index);
+        let fragment = self.owned(vec);
+        let ptr = self.hold(fragment, index, id.generation());
+        self.data[index] = Data::Rp(Some(ptr));
         self.rc += 1;
-        if !skip_calibration{
-            unsafe {self.calibrate_index(len)}
-        }
-        return Some(());
+        self.prefix_vec.0.set(index, len);
+        self.reset_cursor();
+        Some(id)
     }
     pub fn read(&self, index: usize) -> Option<&T>{
-        unsafe {
-            if let Some(immutable) = self.get_raw_mut(index) {
-                return Some(immutable.as_ref())
-            }else {
-                return None;
+        let (rough_index, offset) = self.locate(index)?;
+        match &self.data[rough_index] {
+            Data::Val(v) => Some(v),
+            Data::Rp(Some(pointer)) => {
+                let fragment = unsafe { pointer.as_ref() };
+                match fragment.get(offset) {
+                    None => panic!("read failed, index is out of bounds, index is {}, len is: {}", offset, fragment.len()),
+                    v => v,
+                }
             }
+            Data::Rp(None) => None,
         }
     }
+    //None for holes and for elements of a read only fragment
     pub fn write(&mut self, index: usize, val: T) -> Option<()>{
         unsafe {
             if let Some(mutable) = self.get_raw_mut(index) {
                 mutable.replace(val);
 
-                return Some(());
+                Some(())
             }else {
-                return None;
+                None
             }
         }
     }
-    pub unsafe fn get_raw_mut(&self, index: usize) -> Option<NonNull<T>>{
-        let target = index + 1;
-        let rough_index = self.prefix_vec.0.partition_point(|&x| x < target);
-        if rough_index >= self.data.len(){
-            panic!("index out of bounds");
+    //remembers the last slot that was hit so walking forward doesn't search the prefix index every time
+    pub fn set_cursor_cache(&mut self, enabled: bool){
+        self.cursor = if enabled { Some(CursorCache::default()) } else { None };
+    }
+
+    fn reset_cursor(&mut self){
+        if let Some(cursor) = &mut self.cursor {
+            cursor.reset();
+        }
+    }
+
+    fn locate(&self, index: usize) -> Option<(usize, usize)>{
+        let cursor = match &self.cursor {
+            Some(cursor) => cursor,
+            None => return self.prefix_vec.0.find(index),
+        };
+        if let Some((slot, start, end)) = cursor.load() {
+            if index >= start && index < end {
+                return Some((slot, index - start));
+            }
+            if index >= end && slot + 1 < self.prefix_vec.0.len() {
+                let next_end = end + self.prefix_vec.0.get(slot + 1);
+                if index < next_end {
+                    cursor.store(slot + 1, end, next_end);
+                    return Some((slot + 1, index - end));
+                }
+            }
         }
-        return match &self.data[rough_index] {
+        let (slot, offset) = self.prefix_vec.0.find(index)?;
+        let start = index - offset;
+        cursor.store(slot, start, start + self.prefix_vec.0.get(slot));
+        Some((slot, offset))
+    }
+
+    /// # Safety
+    ///
+    /// Goes through `&self` but may copy a fragment that is shared with a clone or a snapshot and write the copy
+    /// into its slot. Nothing else may touch this `MutedIn` until the pointer is no longer used, so the caller
+    /// needs the access `&mut self` would give it, the way `write` has it.
+    pub unsafe fn get_raw_mut(&self, index: usize) -> Option<NonNull<T>>{
+        let (rough_index, offset) = self.locate(index)?;
+        match &self.data[rough_index] {
             Data::Val(v) => Some(NonNull::from(v)),
             Data::Rp(p) => {
                 if let Some(pointer) = p{
                     unsafe {
-                        let vec: &mut Vec<Data<T>> = &mut *pointer.as_ptr();
-                        let offset = if rough_index == 0{
-                            index
-                        }else {
-                            index - self.prefix_vec.0[rough_index - 1]
-                        };
-                        let len = vec.len();
-                        match vec.get_mut(offset) {
-                            None => panic!("read or write failed, index is out of bounds, index is {}, len is: {}", offset, len),
-                            Some(Data::Val(v)) => Some(NonNull::from(v)),
-                            Some(_) => unreachable!(), //nested pointers, later, maybe?
+                        let fragment: &mut Fragment<T> = &mut *pointer.as_ptr();
+                        let len = fragment.len();
+                        if offset >= len {
+                            panic!("read or write failed, index is out of bounds, index is {}, len is: {}", offset, len);
                         }
+                        fragment.get_mut(offset, self.cloner.get()).map(NonNull::from)
                     }
                 }else {
                     None
                 }
             }
 
+        }
+    }
+    
+}
+
+impl<'a, T: Hash + Eq + Debug + Clone, P: PrefixIndex, A: Allocator + Clone> MutedIn<'a, T, P, A>{
+    //like Vec::truncate, whole slots past len go and the fragment len lands in is shortened
+    //needs Clone since shortening a fragment that is shared (with a clone or through push_shared) copies what is left
+    pub fn truncate(&mut self, len: usize){
+        if len >= self.len() {
+            return;
+        }
+        while let Some(last) = self.data.len().checked_sub(1) {
+            if self.prefix_vec.0.prefix(last) < len {
+                break;
+            }
+            if let Some(Data::Rp(Some(ptr))) = self.data.pop() {
+                std::mem::drop(self.release(ptr));
+                self.rc -= 1;
+            }
+        }
+        self.prefix_vec.0.truncate(self.data.len());
+        self.reset_cursor();
+        let last = match self.data.len().checked_sub(1) {
+            Some(last) => last,
+            None => return,
         };
+        let keep = len - self.prefix_vec.0.prefix(last);
+        if keep >= self.prefix_vec.0.get(last) {
+            return;
+        }
+        //a Val is 1 long and a hole 0 so only a fragment can be cut in two
+        let ptr = self.slot_ptr(last).expect("only a fragment can be cut");
+        match unsafe { &mut (*ptr.as_ptr()).0 } {
+            Repr::Owned(vec) => match Arc::get_mut(vec) {
+                Some(vec) => vec.truncate(keep),
+                None => *vec = Arc::new(vec[..keep].to_vec()),
+            },
+            Repr::Unique(vec) => vec.truncate(keep),
+            Repr::Shared(shared) => *shared = Arc::from(&shared[..keep]),
+            Repr::Borrowed(borrowed) => *borrowed = NonNull::from(&unsafe { borrowed.as_ref() }[..keep]),
+        }
+        self.resize_held(ptr, keep);
+        self.prefix_vec.0.set(last, keep);
     }
 
-    unsafe fn calibrate_index(&mut self, added_len: usize){
-        let added_len = added_len as usize;
-        let start = self.prefix_vec.1;
-        for i in start..self.prefix_vec.0.len(){
-            self.prefix_vec.0[i] += added_len;
-        }   
-        
+    //takes the last element off whatever slot holds it, holes and empty fragments behind it go with it
+    //a fragment that ends up empty goes too, its id stops resolving
+    //needs Clone for the same reason truncate does, a shared or borrowed fragment hands out a copy
+    //O(1) amortized, the walk back only passes slots that get dropped here
+    pub fn pop(&mut self) -> Option<T>{
+        if self.is_empty() {
+            return None;
+        }
+        let mut slot = self.data.len() - 1;
+        while self.prefix_vec.0.get(slot) == 0 {
+            slot -= 1;
+        }
+        let offset = self.prefix_vec.0.get(slot) - 1;
+        while self.data.len() > slot + 1 {
+            if let Some(Data::Rp(Some(ptr))) = self.data.pop() {
+                std::mem::drop(self.release(ptr));
+                self.rc -= 1;
+            }
+        }
+        self.prefix_vec.0.truncate(slot + 1);
+        self.reset_cursor();
+        let ptr = match self.slot_ptr(slot) {
+            Some(ptr) => ptr,
+            None => {
+                self.prefix_vec.0.truncate(slot);
+                return match self.data.pop() {
+                    Some(Data::Val(v)) => Some(v),
+                    _ => unreachable!("a slot with an element in it is a Val or a fragment"),
+                };
+            }
+        };
+        let popped = match unsafe { &mut (*ptr.as_ptr()).0 } {
+            Repr::Owned(vec) => Arc::make_mut(vec).pop(),
+            Repr::Unique(vec) => vec.pop(),
+            Repr::Shared(shared) => {
+                let v = shared[offset].clone();
+                *shared = Arc::from(&shared[..offset]);
+                Some(v)
+            }
+            Repr::Borrowed(borrowed) => {
+                let elements = unsafe { borrowed.as_ref() };
+                let v = elements[offset].clone();
+                *borrowed = NonNull::from(&elements[..offset]);
+                Some(v)
+            }
+        };
+        if offset == 0 {
+            self.data.pop();
+            std::mem::drop(self.release(ptr));
+            self.rc -= 1;
+            self.prefix_vec.0.truncate(slot);
+        } else {
+            self.resize_held(ptr, offset);
+            self.prefix_vec.0.set(slot, offset);
+        }
+        popped
     }
-    
-}
\ No newline at end of file
+}
+
+impl<'b, T: Hash + Eq + Debug, P: PrefixIndex, A: Allocator + Clone> FragmentedVec<T> for MutedIn<'b, T, P, A>{
+    type Iter<'a> = Iter<'a, T> where Self: 'a;
+
+    fn len(&self) -> usize {
+        MutedIn::len(self)
+    }
+    fn read(&self, index: usize) -> Option<&T> {
+        MutedIn::read(self, index)
+    }
+    fn write(&mut self, index: usize, val: T) -> Option<()> {
+        MutedIn::write(self, index, val)
+    }
+    fn push_vec(&mut self, other: Vec<T>) -> FragmentId {
+        MutedIn::push_vec(self, other)
+    }
+    fn drop_fragment(&mut self, id: FragmentId) -> Option<()> {
+        MutedIn::drop_fragment(self, id)
+    }
+    fn take_fragment(&mut self, at: impl Into<FragmentRef>) -> Option<Vec<T>> {
+        MutedIn::take_fragment(self, at)
+    }
+    fn insert_fragment(&mut self, slot: usize, other: Vec<T>) -> Option<FragmentId> {
+        self.insert_vec(slot, other)
+    }
+    fn fragment(&self, id: FragmentId) -> Option<&[T]> {
+        MutedIn::fragment(self, id)
+    }
+    fn fragment_mut(&mut self, id: FragmentId) -> Option<&mut [T]> {
+        MutedIn::fragment_mut(self, id)
+    }
+    fn fragment_range(&self, id: FragmentId) -> Option<Range<usize>> {
+        MutedIn::fragment_range(self, id)
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        MutedIn::iter(self)
+    }
+    fn compact(&mut self) {
+        MutedIn::compact(self)
+    }
+}
+
+//frozen copy of a Muted, inline values are copied and fragments are shared, nothing the Muted does later shows up here
+//cheap to clone and safe to hand to other threads while the Muted keeps taking push_vec/write
+#[derive(Debug)]
+pub struct MutedSnapshot<T: Eq + Hash, P: PrefixIndex = FenwickIndex> {
+    slots: Arc<Vec<Frozen<T>>>,
+    prefix_vec: Arc<P>,
+}
+
+#[derive(Debug)]
+enum Frozen<T: Eq + Hash> {
+    Val(T),
+    Fragment(Fragment<T>),
+    Hole,
+}
+
+impl<T: Eq + Hash, P: PrefixIndex> Clone for MutedSnapshot<T, P> {
+    fn clone(&self) -> Self {
+        MutedSnapshot { slots: Arc::clone(&self.slots), prefix_vec: Arc::clone(&self.prefix_vec) }
+    }
+}
+
+impl<T: Eq + Hash, P: PrefixIndex> MutedSnapshot<T, P> {
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.prefix_vec.total()
+    }
+
+    pub fn read(&self, index: usize) -> Option<&T> {
+        let (slot, offset) = self.prefix_vec.find(index)?;
+        match &self.slots[slot] {
+            Frozen::Val(v) => Some(v),
+            Frozen::Fragment(fragment) => fragment.get(offset),
+            Frozen::Hole => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().flat_map(|slot| match slot {
+            Frozen::Val(v) => slice::from_ref(v).iter(),
+            Frozen::Fragment(fragment) => fragment.iter(),
+            Frozen::Hole => [].iter(),
+        })
+    }
+}
+
+impl<'a, T: Hash + Eq + Debug + Clone, P: PrefixIndex, A: Allocator + Clone> MutedIn<'a, T, P, A>{
+    //from here on a write into a shared fragment copies it first
+    fn share_fragments(&self) -> ClonerFn<T>{
+        *self.cloner.0.get_or_init(|| <[T]>::to_vec)
+    }
+}
+
+//only for Muted, a borrowed fragment would tie the snapshot to the borrow
+impl<T: Hash + Eq + Debug + Clone, P: PrefixIndex + Clone, A: Allocator + Clone> MutedIn<'static, T, P, A>{
+    //O(slots) like clone(), no element of a fragment is copied
+    //except in arena mode, arena fragments have no Arc to share so every one of them is copied, O(len) then
+    pub fn snapshot(&self) -> MutedSnapshot<T, P>{
+        let cloner = self.share_fragments();
+        let slots = self.data.iter().map(|item| match item {
+            Data::Val(v) => Frozen::Val(v.clone()),
+            Data::Rp(Some(p)) => Frozen::Fragment(unsafe { p.as_ref() }.share(cloner)),
+            Data::Rp(None) => Frozen::Hole,
+        }).collect();
+        MutedSnapshot { slots: Arc::new(slots), prefix_vec: Arc::new(self.prefix_vec.0.clone()) }
+    }
+}
+
+//one unit of work for par_chunks, a run of inline values still sits in Data slots, a fragment is plain elements
+#[cfg(feature = "rayon")]
+pub enum Chunk<'a, T: Eq + Hash> {
+    Slots(&'a [Data<T>]),
+    Elements(&'a [T]),
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Eq + Hash> Chunk<'a, T> {
+    pub fn len(&self) -> usize {
+        match self {
+            Chunk::Slots(slots) => slots.len(),
+            Chunk::Elements(elements) => elements.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> ChunkIter<'a, T> {
+        match self {
+            Chunk::Slots(slots) => ChunkIter::Slots(slots.iter()),
+            Chunk::Elements(elements) => ChunkIter::Elements(elements.iter()),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+pub enum ChunkIter<'a, T: Eq + Hash> {
+    Slots(slice::Iter<'a, Data<T>>),
+    Elements(slice::Iter<'a, T>),
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Eq + Hash> Iterator for ChunkIter<'a, T>{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ChunkIter::Slots(slots) => slots.next().map(|item| match item {
+                Data::Val(v) => v,
+                Data::Rp(_) => unreachable!("please no nested pointers (may be added later)"),
+            }),
+            ChunkIter::Elements(elements) => elements.next(),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+enum ChunkMut<'a, T: Eq + Hash> {
+    Slots(slice::IterMut<'a, Data<T>>),
+    Elements(slice::IterMut<'a, T>),
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Eq + Hash> Iterator for ChunkMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ChunkMut::Slots(slots) => slots.next().map(|item| match item {
+                Data::Val(v) => v,
+                Data::Rp(_) => unreachable!("please no nested pointers (may be added later)"),
+            }),
+            ChunkMut::Elements(elements) => elements.next(),
+        }
+    }
+}
+
+//work is split along slot boundaries, every chunk is either a whole fragment or a run of inline values
+#[cfg(feature = "rayon")]
+impl<'b, T: Hash + Eq + Debug + Send + Sync, P: PrefixIndex, A: Allocator + Clone> MutedIn<'b, T, P, A>{
+    pub fn par_chunks(&self) -> impl IndexedParallelIterator<Item = Chunk<'_, T>> {
+        let mut chunks: Vec<Chunk<'_, T>> = Vec::new();
+        let mut run = 0;
+        for (slot, item) in self.data.iter().enumerate() {
+            if let Data::Rp(rp) = item {
+                if run < slot {
+                    chunks.push(Chunk::Slots(&self.data[run..slot]));
+                }
+                run = slot + 1;
+                if let Some(p) = rp {
+                    chunks.push(Chunk::Elements(unsafe { p.as_ref() }.as_slice()));
+                }
+            }
+        }
+        if run < self.data.len() {
+            chunks.push(Chunk::Slots(&self.data[run..]));
+        }
+        chunks.into_par_iter()
+    }
+
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = &T> {
+        self.par_chunks().flat_map_iter(|chunk| chunk.iter())
+    }
+
+    //elements of read only fragments (push_shared/push_slice) are not visited, the same way write refuses them,
+    //so this can hand out fewer than len() elements, push_vec a copy of them first if they need changing too
+    //owned fragments a clone still shares are copied first like write does
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut T> {
+        let mut chunks: Vec<ChunkMut<'_, T>> = Vec::new();
+        let mut rest: &mut [Data<T>] = &mut self.data[..];
+        while !rest.is_empty() {
+            let split = rest.iter().position(|item| matches!(item, Data::Rp(_))).unwrap_or(rest.len());
+            let (run, tail) = rest.split_at_mut(split);
+            if !run.is_empty() {
+                chunks.push(ChunkMut::Slots(run.iter_mut()));
+            }
+            match tail.split_first_mut() {
+                Some((slot, tail)) => {
+                    //every fragment has its own box or arena entry and its own element buffer so the slices never overlap
+                    if let Data::Rp(Some(p)) = slot {
+                        if let Some(elements) = unsafe { p.as_mut() }.make_mut(self.cloner.get()) {
+                            chunks.push(ChunkMut::Elements(elements.iter_mut()));
+                        }
+                    }
+                    rest = tail;
+                }
+                None => break,
+            }
+        }
+        chunks.into_par_iter().flat_map_iter(|chunk| chunk)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Hash + Eq + Debug + Serialize> Serialize for Data<T>{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Data::Val(v) => v.serialize(serializer),
+            Data::Rp(_) => Err(serde::ser::Error::custom("please no nested pointers (may be added later)")),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'b, T: Hash + Eq + Debug + Serialize, P: PrefixIndex, A: Allocator + Clone> Serialize for MutedIn<'b, T, P, A>{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Hash + Eq + Debug + Deserialize<'de>, P: PrefixIndex> Deserialize<'de> for Muted<T, P>{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<T>::deserialize(deserializer).map(Self::new_with_index)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'b, T: Hash + Eq + Debug + Serialize, P: PrefixIndex, A: Allocator + Clone> Serialize for WithFragments<&MutedIn<'b, T, P, A>>{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.data.iter().map(|item| match item {
+            Data::Val(v) => SlotRef::Val(v),
+            Data::Rp(Some(p)) => SlotRef::Fragment(unsafe { p.as_ref() }.as_slice()),
+            Data::Rp(None) => SlotRef::<T, T>::Hole,
+        }))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Hash + Eq + Debug + Deserialize<'de>, P: PrefixIndex> Deserialize<'de> for WithFragments<Muted<T, P>>{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let slots = Vec::<Slot<T>>::deserialize(deserializer)?;
+        let mut muted: Muted<T, P> = Muted::new_with_index(vec![]);
+        for slot in slots {
+            match slot {
+                Slot::Val(v) => muted.push_inline(v),
+                Slot::Fragment(vec) => { muted.push_vec(vec); }
+                Slot::Hole => muted.push_hole(),
+            }
+        }
+        Ok(WithFragments(muted))
+    }
+}
+
+//native binary format, see binary.rs for the layout
+impl<'b, T: Hash + Eq + Debug + Pod, P: PrefixIndex, A: Allocator + Clone> MutedIn<'b, T, P, A>{
+    pub fn write_to(&self, out: impl Write) -> io::Result<()>{
+        let mut out = FormatWriter::new(out);
+        let header = Header{elem_size: std::mem::size_of::<T>(), slots: self.data.len(), len: self.len()};
+        out.bytes(&header.encode())?;
+        for (slot, item) in self.data.iter().enumerate() {
+            let kind = match item {
+                Data::Val(_) => SLOT_VAL,
+                Data::Rp(Some(_)) => SLOT_FRAGMENT,
+                Data::Rp(None) => SLOT_HOLE,
+            };
+            out.bytes(&encode_slot(kind, self.prefix_vec.0.prefix(slot), self.prefix_vec.0.get(slot)))?;
+        }
+        for item in self.data.iter() {
+            match item {
+                Data::Val(v) => out.elements(slice::from_ref(v))?,
+                Data::Rp(Some(p)) => out.elements(unsafe { p.as_ref() }.as_slice())?,
+                Data::Rp(None) => (),
+            }
+        }
+        out.finish()
+    }
+
+    pub fn read_from_in(input: impl Read, alloc: A) -> io::Result<Self>{
+        let mut input = FormatReader::new(input);
+        let header = input.header::<T>()?;
+        let table = input.table(&header)?;
+        let mut muted: MutedIn<'b, T, P, A> = MutedIn::new_in(vec![], alloc);
+        let mut slot = 0;
+        while slot < table.len() {
+            match table[slot] {
+                //runs of inline values come in one read
+                (SLOT_VAL, _) => {
+                    let run = table[slot..].iter().take_while(|(kind, _)| *kind == SLOT_VAL).count();
+                    for val in input.elements::<T>(run)? {
+                        muted.push_inline(val);
+                    }
+                    slot += run;
+                    continue;
+                }
+                (SLOT_FRAGMENT, len) => { muted.push_vec(input.elements(len)?); }
+                _ => muted.push_hole(),
+            }
+            slot += 1;
+        }
+        input.finish()?;
+        Ok(muted)
+    }
+}
+
+impl<'b, T: Hash + Eq + Debug + Pod, P: PrefixIndex> MutedIn<'b, T, P>{
+    pub fn read_from(input: impl Read) -> io::Result<Self>{
+        Self::read_from_in(input, Global)
+    }
+}
@@ -1,9 +1,30 @@
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+pub mod allocator;
+pub mod binary;
+pub mod builder;
+pub mod concurrent;
+pub mod fragmented;
 pub mod generic;
+#[cfg(feature = "memmap2")]
+pub mod mapped;
 pub mod primitive;
 mod raw_buf;
+pub mod prefix_index;
+#[cfg(feature = "serde")]
+pub mod serialize;
 pub mod NightlyGeneric;
 
-pub use generic::Muted;
+pub use allocator::{Allocator, Global};
+pub use binary::Pod;
+pub use builder::MutedBuilder;
+pub use concurrent::{ConcurrentMuted, ConcurrentSnapshot};
+pub use fragmented::{CompactPolicy, FragmentId, FragmentRef, FragmentedVec, MutedStats};
+pub use generic::{Fragment, Muted, MutedIn, MutedSnapshot};
+#[cfg(feature = "memmap2")]
+pub use mapped::MappedMuted;
 pub use raw_buf::RawBuf;
 pub use NightlyGeneric::Muted as UnionMuted;
-pub use primitive::PrimitiveMuted;
+pub use primitive::{PrimitiveMuted, PrimitiveWidth};
+pub use prefix_index::{FenwickIndex, LinearIndex, PrefixIndex};
+#[cfg(feature = "serde")]
+pub use serialize::WithFragments;
@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io;
+use std::marker::PhantomData;
+use std::mem;
+use std::path::Path;
+use std::slice;
+use memmap2::Mmap;
+use crate::binary::{decode_slot, Checksum, Header, Pod, HEADER_LEN, SLOT_ENTRY_LEN};
+use crate::prefix_index::{LinearIndex, PrefixIndex};
+
+//read only view over a file written by write_to, nothing is loaded up front except the slot table
+//the file keeps every element in logical order so fragments are just ranges of the mapping
+pub struct MappedMuted<T: Pod> {
+    map: Mmap,
+    //byte offset of the first element
+    data_start: usize,
+    len: usize,
+    //slot lengths from the table, only needed for chunks, reads go straight into the mapping
+    prefix_vec: LinearIndex,
+    marker: PhantomData<T>,
+}
+
+impl<T: Pod> MappedMuted<T> {
+    /// # Safety
+    ///
+    /// Same contract as `Mmap::map`: the file must not be written to, truncated or replaced, by this process or
+    /// any other, for as long as the `MappedMuted` is alive. The slices it hands out would change or vanish under it.
+    pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Self::from_mmap(Mmap::map(&file)?)
+    }
+
+    pub fn from_mmap(map: Mmap) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        if map.len() < HEADER_LEN {
+            return Err(invalid("file too short for a header"));
+        }
+        let header = Header::decode::<T>(map[..HEADER_LEN].try_into().unwrap())?;
+        let data_start = header.slots.checked_mul(SLOT_ENTRY_LEN)
+            .and_then(|table| table.checked_add(HEADER_LEN))
+            .ok_or_else(|| invalid("slot table too big"))?;
+        let expected = header.len.checked_mul(mem::size_of::<T>())
+            .and_then(|data| data.checked_add(data_start))
+            .and_then(|data| data.checked_add(8));
+        if expected != Some(map.len()) {
+            return Err(invalid("file length doesn't match the header"));
+        }
+        if !(map.as_ptr() as usize + data_start).is_multiple_of(mem::align_of::<T>()) {
+            return Err(invalid("elements are not aligned for T"));
+        }
+
+        let mut lens = Vec::with_capacity(header.slots);
+        let mut offset = 0;
+        for entry in map[HEADER_LEN..data_start].chunks_exact(SLOT_ENTRY_LEN) {
+            let (_, _, len) = decode_slot(entry, offset)?;
+            offset = offset.checked_add(len).ok_or_else(|| invalid("slot lengths overflow"))?;
+            lens.push(len);
+        }
+        if offset != header.len {
+            return Err(invalid("slot table doesn't add up to the length in the header"));
+        }
+
+        Ok(MappedMuted {
+            map,
+            data_start,
+            len: header.len,
+            prefix_vec: LinearIndex::from_lens(lens),
+            marker: PhantomData,
+        })
+    }
+
+    //reads the whole file, so it's a separate step instead of part of open
+    pub fn verify(&self) -> io::Result<()> {
+        let end = self.map.len() - 8;
+        let mut sum = Checksum::new();
+        sum.update(&self.map[..end]);
+        if u64::from_le_bytes(self.map[end..].try_into().unwrap()) != sum.get() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "checksum mismatch"));
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    //every live element in logical order, straight out of the mapping
+    pub fn as_slice(&self) -> &[T] {
+        //from_mmap checked the length and the alignment, any bit pattern is a valid T
+        unsafe { slice::from_raw_parts(self.map.as_ptr().add(self.data_start) as *const T, self.len) }
+    }
+
+    pub fn read(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    //one slice per slot like PrimitiveMuted::chunks, holes are skipped
+    pub fn chunks(&self) -> impl Iterator<Item = &[T]> {
+        let all = self.as_slice();
+        (0..self.prefix_vec.len()).filter_map(move |slot| {
+            let len = self.prefix_vec.get(slot);
+            if len == 0 {
+                return None;
+            }
+            let start = self.prefix_vec.prefix(slot);
+            Some(&all[start..start + len])
+        })
+    }
+
+    //number of slots in the file, holes included
+    pub fn slots(&self) -> usize {
+        self.prefix_vec.len()
+    }
+}
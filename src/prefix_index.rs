@@ -0,0 +1,237 @@
+//maps logical element indices to slots
+//a slot is either one inline value (len 1), a fragment (len of the fragment) or a hole (len 0)
+//the containers only ever talk to this trait so the layout can be swapped depending on the workload
+pub trait PrefixIndex: Default {
+    fn from_lens(lens: Vec<usize>) -> Self;
+    //number of slots
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    //number of elements over all slots
+    fn total(&self) -> usize;
+    fn get(&self, slot: usize) -> usize;
+    fn push(&mut self, len: usize);
+    fn set(&mut self, slot: usize, len: usize);
+    //logical index of the first element of slot
+    fn prefix(&self, slot: usize) -> usize;
+    //slot holding the element at index and the offset inside it, holes are skipped since they have no length
+    fn find(&self, index: usize) -> Option<(usize, usize)>;
+    fn clear(&mut self);
+    //keeps the first slots slots, rebuilding is the fallback
+    fn truncate(&mut self, slots: usize) {
+        if slots < self.len() {
+            *self = Self::from_lens((0..slots).map(|slot| self.get(slot)).collect());
+        }
+    }
+    //room for that many more slots, only a hint
+    fn reserve(&mut self, _additional: usize) {}
+    fn shrink_to_fit(&mut self) {}
+    //for stats(), 0 unless the index knows better
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+}
+
+//fenwick tree over the slot lengths, lookups, pushes and length changes of any slot are all O(log n)
+//pushing and changing the last slot are cheaper, see push and set
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct FenwickIndex {
+    tree: Vec<usize>,
+    lens: Vec<usize>,
+    total: usize,
+}
+
+//the old plain cumulative layout, O(log n) lookups but changing a slot rewrites every prefix after it
+//still the better pick when fragments are only ever pushed
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct LinearIndex {
+    ends: Vec<usize>,
+}
+
+impl PrefixIndex for FenwickIndex {
+    //O(n) build, every node only pushes its sum to its direct parent
+    fn from_lens(lens: Vec<usize>) -> Self {
+        let mut tree = lens.clone();
+        for i in 1..=tree.len() {
+            let parent = i + lowbit(i);
+            if parent <= tree.len() {
+                tree[parent - 1] += tree[i - 1];
+            }
+        }
+        let total = lens.iter().sum();
+        FenwickIndex { tree, lens, total }
+    }
+
+    fn len(&self) -> usize {
+        self.lens.len()
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+
+    fn get(&self, slot: usize) -> usize {
+        self.lens[slot]
+    }
+
+    //the new node sums its direct children, on average there is about one so pushing is O(1) amortized
+    fn push(&mut self, len: usize) {
+        let i = self.lens.len() + 1;
+        let below = i - lowbit(i);
+        let mut node = len;
+        let mut child = i - 1;
+        while child > below {
+            node += self.tree[child - 1];
+            child -= lowbit(child);
+        }
+        self.tree.push(node);
+        self.lens.push(len);
+        self.total += len;
+    }
+
+    //no node past the last one covers the last slot, so setting it (push/pop on the tail) only touches one node
+    fn set(&mut self, slot: usize, len: usize) {
+        let old = self.lens[slot];
+        if old == len {
+            return;
+        }
+        self.lens[slot] = len;
+        self.total = self.total - old + len;
+        let mut i = slot + 1;
+        while i <= self.tree.len() {
+            self.tree[i - 1] = self.tree[i - 1] - old + len;
+            i += lowbit(i);
+        }
+    }
+
+    fn prefix(&self, slot: usize) -> usize {
+        let mut sum = 0;
+        let mut i = slot;
+        while i > 0 {
+            sum += self.tree[i - 1];
+            i -= lowbit(i);
+        }
+        sum
+    }
+
+    fn find(&self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.total {
+            return None;
+        }
+        let mut pos = 0;
+        let mut rem = index;
+        let mut step = highbit(self.tree.len());
+        while step > 0 {
+            let next = pos + step;
+            if next <= self.tree.len() && self.tree[next - 1] <= rem {
+                pos = next;
+                rem -= self.tree[next - 1];
+            }
+            step >>= 1;
+        }
+        Some((pos, rem))
+    }
+
+    fn clear(&mut self) {
+        self.tree.clear();
+        self.lens.clear();
+        self.total = 0;
+    }
+
+    //a node only ever sums slots at or below it so the first slots nodes stay as they are
+    //costs as many steps as slots go, so popping off the tail stays O(1)
+    fn truncate(&mut self, slots: usize) {
+        if slots < self.len() {
+            self.total -= self.lens[slots..].iter().sum::<usize>();
+            self.tree.truncate(slots);
+            self.lens.truncate(slots);
+        }
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.tree.reserve(additional);
+        self.lens.reserve(additional);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.tree.shrink_to_fit();
+        self.lens.shrink_to_fit();
+    }
+
+    fn heap_bytes(&self) -> usize {
+        (self.tree.capacity() + self.lens.capacity()) * size_of::<usize>()
+    }
+}
+
+impl PrefixIndex for LinearIndex {
+    fn from_lens(lens: Vec<usize>) -> Self {
+        let mut total = 0;
+        let ends = lens.iter().map(|len| { total += len; total }).collect();
+        LinearIndex { ends }
+    }
+
+    fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    fn total(&self) -> usize {
+        self.ends.last().cloned().unwrap_or(0)
+    }
+
+    fn get(&self, slot: usize) -> usize {
+        self.ends[slot] - self.prefix(slot)
+    }
+
+    fn push(&mut self, len: usize) {
+        let total = self.total();
+        self.ends.push(total + len);
+    }
+
+    fn set(&mut self, slot: usize, len: usize) {
+        let old = self.get(slot);
+        for end in self.ends[slot..].iter_mut() {
+            *end = *end - old + len;
+        }
+    }
+
+    fn prefix(&self, slot: usize) -> usize {
+        if slot == 0 { 0 } else { self.ends[slot - 1] }
+    }
+
+    fn find(&self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.total() {
+            return None;
+        }
+        let slot = self.ends.partition_point(|&end| end <= index);
+        Some((slot, index - self.prefix(slot)))
+    }
+
+    fn clear(&mut self) {
+        self.ends.clear();
+    }
+
+    fn truncate(&mut self, slots: usize) {
+        self.ends.truncate(slots);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.ends.reserve(additional);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.ends.shrink_to_fit();
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.ends.capacity() * size_of::<usize>()
+    }
+}
+
+fn lowbit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+fn highbit(n: usize) -> usize {
+    if n == 0 { 0 } else { 1 << (usize::BITS - 1 - n.leading_zeros()) }
+}
@@ -1,59 +1,148 @@
-use std::any::type_name_of_val;
-use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
-use std::mem;
+use std::io::{self, Read, Write};
 use std::mem::ManuallyDrop;
+use std::ops::Range;
 use std::ptr::NonNull;
+use std::slice;
+use crate::binary::{encode_slot, FormatReader, FormatWriter, Header, Pod, SLOT_FRAGMENT, SLOT_HOLE, SLOT_VAL};
+use crate::fragmented::{CompactPolicy, FragmentId, FragmentRef, FragmentedVec, MutedStats};
+use crate::prefix_index::{FenwickIndex, PrefixIndex};
+use crate::raw_buf::{offset::Bit, RawBuf};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "serde")]
+use crate::serialize::{Slot, SlotRef, WithFragments};
+
+//2 bits per slot in variant_marker, same layout as NightlyGeneric
+//the union itself can't tell an inline integer from a pointer so this is the only source of truth
+const HOLE: u8 = 0;
+const RP: u8 = 1;
+const VALUE: u8 = 2;
 
 #[repr(C, align(8))]
 union PrimitiveData<T: Copy + Hash + Eq>{
     val: T,
-    rp: Option<NonNull<Vec<PrimitiveData<T>>>>,
+    rp: Option<NonNull<Vec<T>>>,
+}
+
+//(fragment, len, generation)
+//the pointer comes straight out of Box::into_raw and is the same one data holds, every access goes through it
+type Held<T> = (NonNull<Vec<T>>, usize, u32);
+
+fn free<T>(held: Held<T>) {
+    std::mem::drop(unsafe { Box::from_raw(held.0.as_ptr()) });
 }
 
-pub struct PrimitiveMuted<T: Hash + Eq + Debug + Copy + Debug + Display> {
+pub struct PrimitiveMuted<T: Hash + Eq + Debug + Copy + Debug + Display, P: PrefixIndex = FenwickIndex> {
     data: Box<Vec<PrimitiveData<T>>>,
-    r_hold: HashMap<NonNull<Vec<PrimitiveData<T>>>, Option<(ManuallyDrop<Box<Vec<PrimitiveData<T>>>>, usize, usize)>>,
+    //one entry per slot: (fragment, len, generation) or None for inline values and holes
+    //the start of a fragment is not kept here since the prefix index already knows it
+    r_hold: Vec<Option<Held<T>>>,
+    variant_marker: RawBuf,
     index_offset: isize,
-    prefix_vec: (Vec<usize>, usize),
+    prefix_vec: (P, usize),
     t_is_32: bool,
     rc: usize,
+    generation: u32,
+    //same as in generic::Muted, vecs shorter than this are stored as VALUE slots, 0 never inlines
+    inline_below: usize,
+    //(policy, drops since the last compact)
+    compaction: (CompactPolicy, usize),
 }
 
-impl<T: Hash + Eq + Debug + Copy + Debug + Display> Display for PrimitiveMuted<T>{
+//what PrimitiveMuted::new's is_32bit picks, MutedBuilder takes this instead of the bare bool
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PrimitiveWidth {
+    Bits32,
+    Bits64,
+}
+
+//fragments are owned through r_hold, the pointers in data are never handed out on their own
+//both hold the same raw pointer from Box::into_raw, there is no box left that could invalidate it
+unsafe impl<T: Hash + Eq + Debug + Copy + Display + Send, P: PrefixIndex + Send> Send for PrimitiveMuted<T, P> {}
+unsafe impl<T: Hash + Eq + Debug + Copy + Display + Sync, P: PrefixIndex + Sync> Sync for PrimitiveMuted<T, P> {}
+
+impl<T: Hash + Eq + Debug + Copy + Debug + Display, P: PrefixIndex> Display for PrimitiveMuted<T, P>{
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let len = *(self.prefix_vec.0.last()).unwrap();
-        let mut display_raw: Vec<&T> = Vec::with_capacity(len);
-        unsafe {
-            for i in 0..len{
-                let x = self.get_raw(i).unwrap();
-                display_raw.push(x.0);
+        let display_raw: Vec<&T> = self.iter().collect();
+        write!(f, "{:?}", display_raw)
+    }
+}
+
+impl<T: Hash + Eq + Debug + Copy + Display, P: PrefixIndex> Drop for PrimitiveMuted<T, P>{
+    fn drop(&mut self) {
+        //inline values are Copy so only the fragments need freeing
+        for held in self.r_hold.drain(..).flatten() {
+            free(held);
+        }
+        self.data.clear();
+        self.prefix_vec.0.clear();
+        self.rc = 0;
+    }
+}
+
+pub struct Iter<'a, T: Hash + Eq + Debug + Copy + Display, P: PrefixIndex> {
+    muted: &'a PrimitiveMuted<T, P>,
+    index: usize,
+    len: usize,
+}
+
+impl<'a, T: Hash + Eq + Debug + Copy + Display, P: PrefixIndex> Iterator for Iter<'a, T, P>{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.len {
+            let idx = self.index;
+            self.index += 1;
+            if let Some((v, _)) = unsafe { self.muted.get_raw(idx) } {
+                return Some(v);
             }
-            write!(f, "{:?}", display_raw)
         }
+        None
     }
 }
 
 impl<T: Hash + Eq + Debug + Copy + std::marker::Copy + ToString + std::fmt::Display> PrimitiveMuted<T> {
     pub fn new(other: Vec<T>, is_32bit: bool) -> PrimitiveMuted<T> {
-        let (transmuted, index, len) = unsafe {PrimitiveMuted::transmute_vec(other, is_32bit)};
+        Self::new_with_index(other, is_32bit)
+    }
+}
+
+impl<T: Hash + Eq + Debug + Copy + std::marker::Copy + ToString + std::fmt::Display, P: PrefixIndex> PrimitiveMuted<T, P> {
+    //same as new but with a prefix index other than the default fenwick tree
+    pub fn new_with_index(other: Vec<T>, is_32bit: bool) -> PrimitiveMuted<T, P> {
+        let (transmuted, index, len) = Self::transmute_vec(other, is_32bit);
+        let mut buf = RawBuf::new((len * 2).div_ceil(8).max(1) as u32);
+        buf.fill_pairs(len, VALUE);
 
-        return PrimitiveMuted{
+        PrimitiveMuted{
             data: Box::new(transmuted),
-            r_hold: HashMap::new(),
+            r_hold: (0..len).map(|_| None).collect(),
+            variant_marker: buf,
             index_offset: index as isize,
-            prefix_vec: ((1..len + 1).collect(), len),
+            prefix_vec: (P::from_lens(vec![1; len]), 0),
             t_is_32: is_32bit,
-            rc: 0
+            rc: 0,
+            generation: 0,
+            inline_below: 0,
+            compaction: (CompactPolicy::Manual, 0),
         }
     }
-    unsafe fn transmute_vec(vec: Vec<T>, convert_32: bool) -> (Vec<PrimitiveData<T>>, usize, usize) {
+    //what transmute_vec takes, anything else panics there
+    fn supported(convert_32: bool) -> bool {
+        let supported = if convert_32 { ["i32", "u32"] } else { ["i64", "u64"] };
+        supported.contains(&std::any::type_name::<T>())
+    }
+
+    //64 bit values are taken over without copying, 32 bit ones go through the val field one by one
+    fn transmute_vec(vec: Vec<T>, convert_32: bool) -> (Vec<PrimitiveData<T>>, usize, usize) {
 
         let size_of_t = 8;
         let len = vec.len();
 
-        let type_of = type_name_of_val(&vec[0]);
+        let type_of = std::any::type_name::<T>();
 
         if !convert_32 && !(type_of != "u64" || type_of != "i64"){
             panic!("liar liar pants on fire1 {}", type_of);
@@ -61,58 +150,68 @@ impl<T: Hash + Eq + Debug + Copy + std::marker::Copy + ToString + std::fmt::Disp
         if convert_32 && (type_of == "u64" || type_of == "i64"){
             panic!("liar liar pants on fire2 {}", type_of);
         }
-
-        if convert_32 {
-            if type_of == "i32" {
-                let mut trans: Vec<i64> = Vec::with_capacity(vec.len());
-                let first_pass = mem::transmute::<Vec<T>, Vec<i32>>(vec);
-                for v in first_pass.iter() {
-                    trans.push(*v as i64);
-                }
-                return (mem::transmute::<Vec<i64>, Vec<PrimitiveData<T>>>(trans), size_of_t, len)
-            }
-            if type_of == "u32" {
-                let mut trans: Vec<u64> = Vec::with_capacity(vec.len());
-                let first_pass = mem::transmute::<Vec<T>, Vec<u32>>(vec);
-                for v in first_pass.iter() {
-                    trans.push(*v as u64);
-                }
-                return (mem::transmute::<Vec<u64>, Vec<PrimitiveData<T>>>(trans), size_of_t, len)
-            }
-        }
-
-        if type_of == "i64"{
-            return (mem::transmute::<Vec<T>, Vec<PrimitiveData<T>>>(vec), size_of_t, len)
-        }
-        if type_of == "u64" {
-            return (mem::transmute::<Vec<T>, Vec<PrimitiveData<T>>>(vec), size_of_t, len)
-
-        }else {
+        if !Self::supported(convert_32) {
             panic!("Unsupported type: {}", type_of);
         }
 
+        //T is u64 or i64 here, PrimitiveData<T> is just those bits, but only where u64 is 8 aligned can the buffer
+        //be freed with the layout of the other type, 32 bit targets align u64 to 4 and take the copying path below
+        if !convert_32 && size_of::<T>() == size_of::<PrimitiveData<T>>() && align_of::<T>() == align_of::<PrimitiveData<T>>() {
+            let mut vec = ManuallyDrop::new(vec);
+            let converted = unsafe { Vec::from_raw_parts(vec.as_mut_ptr().cast::<PrimitiveData<T>>(), len, vec.capacity()) };
+            return (converted, size_of_t, len)
+        }
+        //the union is 8 bytes anyway so 32 bit values don't need widening anymore
+        let converted = vec.into_iter().map(|val| PrimitiveData{val}).collect();
+        (converted, size_of_t, len)
     }
 
-    pub fn push_vec(&mut self, other: Vec<T>) {
-        let (trans_owo, _, len) = unsafe {PrimitiveMuted::transmute_vec(other, self.t_is_32)};
-        let mut other = ManuallyDrop::new(Box::new(trans_owo));
-        let raw: *mut Vec<PrimitiveData<T>> = &mut **other;
-        let ptr = NonNull::new(raw).unwrap();
+    //fragments keep their plain Vec<T> layout, no widening needed
+    //vecs below the inline threshold are copied into VALUE slots instead, the id for those refers to no fragment
+    pub fn push_vec(&mut self, other: Vec<T>) -> FragmentId {
+        if other.len() < self.inline_below {
+            let id = self.next_id(self.data.len());
+            for val in other {
+                self.push_inline(val);
+            }
+            return id;
+        }
+        let len = other.len();
+        let ptr = unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(other))) };
         let maybe_ptr = Some(ptr);
-        self.r_hold.insert(ptr , Some((other, self.data.len(), len)));
+        let id = self.next_id(self.data.len());
+        self.r_hold.push(Some((ptr, len, id.generation())));
         self.data.push(PrimitiveData{rp: maybe_ptr});
-        let prefix_len = self.prefix_vec.0.len();
-        let previous = &self.prefix_vec.0[prefix_len - 1];
-        self.prefix_vec.0.push(len + previous);
+        self.mark(self.data.len() - 1, RP);
+        self.prefix_vec.0.push(len);
         self.rc += 1;
+        id
+    }
+
+    pub fn set_inline_threshold(&mut self, threshold: usize){
+        self.inline_below = threshold;
+    }
+
+    pub fn with_inline_threshold(mut self, threshold: usize) -> Self{
+        self.set_inline_threshold(threshold);
+        self
+    }
+
+    pub fn inline_threshold(&self) -> usize{
+        self.inline_below
     }
 
-    pub fn read(&mut self, index: usize) -> Option<&T>{
+    //what new/new_with_index was told through is_32bit
+    pub fn width(&self) -> PrimitiveWidth{
+        if self.t_is_32 { PrimitiveWidth::Bits32 } else { PrimitiveWidth::Bits64 }
+    }
+
+    pub fn read(&self, index: usize) -> Option<&T>{
         unsafe {
-            if let Some(immutable) = self.get_raw_mut(index) {
-                return Some(&*immutable)
+            if let Some(immutable) = self.get_raw(index) {
+                Some(immutable.0)
             }else {
-                return None;
+                None
             }
         }
     }
@@ -120,90 +219,619 @@ impl<T: Hash + Eq + Debug + Copy + std::marker::Copy + ToString + std::fmt::Disp
         unsafe {
             if let Some(mutable) = self.get_raw_mut(index) {
                 *mutable = val;
-                return Some(());
+                Some(())
             }else {
-                return None;
+                None
             }
         }
     }
 
     pub unsafe fn get_raw_mut(&mut self, index: usize) -> Option<&mut T> {
-        let target = index + 1;
-        let len = self.data.len();
-        let rough_index = self.prefix_vec.0.partition_point(|&x| x < target);
-        if rough_index >= len{
-            panic!("index out of bounds");
+        let (rough_index, offset) = self.prefix_vec.0.find(index)?;
+
+        //only touch the union field the tag says is live
+        match self.variant(rough_index) {
+            VALUE => Some(&mut self.data[rough_index].val),
+            RP => self.data[rough_index].rp.and_then(|mut p| p.as_mut().get_mut(offset)),
+            _ => None,
         }
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+    //only live elements, holes left by drop_vec count as zero
+    pub fn len(&self) -> usize {
+        self.prefix_vec.0.total()
+    }
 
-        let data = &mut self.data[rough_index];
-        let ptr = match unsafe{(*data).rp}{
-            Some(ptr) => ptr,
-            None => panic!("Ts is none")
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter{
+            muted: self,
+            index: 0,
+            len: self.prefix_vec.0.total(),
+        }
+    }
+
+    //one slice per slot in order, fragments whole and inline values on their own, holes are skipped
+    pub fn chunks(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.data.len()).filter_map(move |slot| match self.variant(slot) {
+            VALUE => Some(slice::from_ref(unsafe { &self.data[slot].val })),
+            RP => unsafe { self.data[slot].rp }.map(|p| unsafe { p.as_ref() }.as_slice()),
+            _ => None,
+        })
+    }
+
+    pub fn compact(&mut self){
+        let merged: Vec<T> = self.iter().cloned().collect();
+        for held in self.r_hold.drain(..).flatten() {
+            free(held);
+        }
+        self.data.clear();
+        self.variant_marker = RawBuf::new(1);
+        self.prefix_vec.0.clear();
+        self.prefix_vec.1 = 0;
+        self.rc = 0;
+        self.compaction.1 = 0;
+        //nothing left means no fragment, not an empty one
+        if !merged.is_empty() {
+            self.push_vec(merged);
+        }
+    }
+
+    pub fn drop_fragment(&mut self, id: FragmentId) -> Option<()>{
+        self.drop_vec(id)
+    }
+
+    //frees a fragment by slot index or by handle and leaves a hole behind for insert_vec
+    pub fn drop_vec(&mut self, at: impl Into<FragmentRef>) -> Option<()>{
+        let slot = self.resolve(at.into())?;
+        if self.variant(slot) != RP {
+            return None;
+        }
+        let held = self.r_hold.get_mut(slot)?.take()?;
+        free(held);
+        self.data[slot] = PrimitiveData{rp: None};
+        self.mark(slot, HOLE);
+        self.prefix_vec.0.set(slot, 0);
+        self.rc -= 1;
+        self.dropped();
+        Some(())
+    }
+
+    //see CompactPolicy, Manual (the default) never compacts on its own
+    pub fn set_compact_policy(&mut self, policy: CompactPolicy){
+        self.compaction = (policy, 0);
+    }
+
+    fn dropped(&mut self){
+        self.compaction.1 += 1;
+        if self.compaction.0.due(self.compaction.1) {
+            self.compact();
+        }
+    }
+
+    //what MutedBuilder hands over, r_hold has one entry per slot so fragments don't need their own room
+    pub(crate) fn reserve_for(&mut self, slots: usize, prefix: usize){
+        self.data.reserve(slots);
+        self.r_hold.reserve(slots);
+        self.prefix_vec.0.reserve(prefix);
+    }
+
+    //room for additional more slots and prefix entries, fragments bring their own elements
+    pub fn reserve(&mut self, additional: usize){
+        self.reserve_for(additional, additional);
+    }
+
+    //gives back what slots, bookkeeping and fragments hold on top of what they use
+    pub fn shrink_to_fit(&mut self){
+        for held in self.r_hold.iter_mut().flatten() {
+            unsafe { held.0.as_mut() }.shrink_to_fit();
+        }
+        self.data.shrink_to_fit();
+        self.r_hold.shrink_to_fit();
+        self.prefix_vec.0.shrink_to_fit();
+        self.variant_marker.data.shrink_to_fit();
+    }
+
+    //like Vec::truncate, whole slots past len go and the fragment len lands in is shortened
+    pub fn truncate(&mut self, len: usize){
+        if len >= self.len() {
+            return;
+        }
+        while let Some(last) = self.data.len().checked_sub(1) {
+            if self.prefix_vec.0.prefix(last) < len {
+                break;
+            }
+            self.data.pop();
+            //a later push reads the tag of the slot it lands in, don't leave the old one behind
+            self.mark(last, HOLE);
+            if let Some(Some(held)) = self.r_hold.pop() {
+                free(held);
+                self.rc -= 1;
+            }
+        }
+        self.prefix_vec.0.truncate(self.data.len());
+        let last = match self.data.len().checked_sub(1) {
+            Some(last) => last,
+            None => return,
         };
+        let keep = len - self.prefix_vec.0.prefix(last);
+        if keep >= self.prefix_vec.0.get(last) {
+            return;
+        }
+        //a VALUE is 1 long and a hole 0 so only a fragment can be cut in two
+        if let Some(held) = &mut self.r_hold[last] {
+            unsafe { held.0.as_mut() }.truncate(keep);
+            held.1 = keep;
+        }
+        self.prefix_vec.0.set(last, keep);
+    }
 
-        match self.r_hold.get_mut(&ptr) {
-            Some(v) => {
-                return match v {
-                    Some(p) => {
-                        let vec: &mut Vec<PrimitiveData<T>> = &mut *p.0;
-                        let offset = if rough_index == 0{
-                            index
-                        }else {
-                            index - self.prefix_vec.0[rough_index - 1]
-                        };
-                        match vec.get_mut(offset) {
-                            Some(PrimitiveData {val: v}) => Some(v),
-                            None => None
-                        }
-                    },
-                    None => None
-                }
-            },
-            None => {
-                return Some(&mut (*data).val)
+    //drops every element and fragment, the slot capacity stays
+    pub fn clear(&mut self){
+        for held in self.r_hold.drain(..).flatten() {
+            free(held);
+        }
+        self.data.clear();
+        self.variant_marker = RawBuf::new(1);
+        self.prefix_vec.0.clear();
+        self.prefix_vec.1 = 0;
+        self.rc = 0;
+    }
+
+    pub fn fragment_count(&self) -> usize{
+        self.rc
+    }
+
+    pub fn hole_count(&self) -> usize{
+        (0..self.data.len()).filter(|slot| self.variant(*slot) == HOLE).count()
+    }
+
+    //in slots, a fragment takes one however long it is
+    pub fn capacity(&self) -> usize{
+        self.data.capacity()
+    }
+
+    pub fn heap_bytes(&self) -> usize{
+        self.stats().heap_bytes()
+    }
+
+    //O(slots)
+    pub fn stats(&self) -> MutedStats{
+        let fragments = || self.r_hold.iter().flatten().map(|held| unsafe { held.0.as_ref() });
+        let mut stats = MutedStats { len: self.len(), slots: self.data.len(), holes: self.hole_count(), ..MutedStats::default() };
+        stats.count_fragments(fragments().map(Vec::len));
+        stats.slot_bytes = self.data.capacity() * size_of::<PrimitiveData<T>>();
+        stats.fragment_bytes = fragments().map(|vec| vec.capacity() * size_of::<T>()).sum();
+        stats.bookkeeping_bytes = self.r_hold.capacity() * size_of::<Option<Held<T>>>()
+            + self.rc * size_of::<Vec<T>>();
+        stats.prefix_bytes = self.prefix_vec.0.heap_bytes();
+        stats.marker_bytes = self.variant_marker.data.capacity();
+        stats
+    }
+
+    //fragments are stored as plain Vec<T> so there is nothing to unwrap
+    pub fn take_fragment(&mut self, at: impl Into<FragmentRef>) -> Option<Vec<T>>{
+        let slot = self.resolve(at.into())?;
+        if self.variant(slot) != RP {
+            return None;
+        }
+        let held = self.r_hold.get_mut(slot)?.take()?;
+        self.data[slot] = PrimitiveData{rp: None};
+        self.mark(slot, HOLE);
+        self.prefix_vec.0.set(slot, 0);
+        self.rc -= 1;
+        let boxed = unsafe { Box::from_raw(held.0.as_ptr()) };
+        self.dropped();
+        Some(*boxed)
+    }
+
+    pub fn fragment(&self, id: FragmentId) -> Option<&[T]>{
+        let held = self.held(id)?;
+        Some(unsafe { held.0.as_ref() }.as_slice())
+    }
+
+    pub fn fragment_mut(&mut self, id: FragmentId) -> Option<&mut [T]>{
+        self.held(id)?;
+        match &mut self.r_hold[id.slot()] {
+            Some(held) => Some(unsafe { held.0.as_mut() }.as_mut_slice()),
+            None => None,
+        }
+    }
+
+    pub fn fragment_range(&self, id: FragmentId) -> Option<Range<usize>>{
+        let held = self.held(id)?;
+        let start = self.prefix_vec.0.prefix(id.slot());
+        Some(start..start + held.1)
+    }
+
+    //the fragment behind id, if id still refers to the fragment it was issued for
+    fn held(&self, id: FragmentId) -> Option<&Held<T>>{
+        match self.r_hold.get(id.slot()) {
+            Some(Some(held)) if held.2 == id.generation() => Some(held),
+            _ => None,
+        }
+    }
+
+    fn next_id(&mut self, slot: usize) -> FragmentId{
+        self.generation = self.generation.wrapping_add(1);
+        FragmentId::new(slot, self.generation)
+    }
+
+    fn resolve(&self, at: FragmentRef) -> Option<usize>{
+        match at {
+            FragmentRef::Slot(slot) => Some(slot),
+            FragmentRef::Id(id) => {
+                self.held(id)?;
+                Some(id.slot())
             }
         }
+    }
+
+    fn push_inline(&mut self, val: T){
+        let slot = self.data.len();
+        self.data.push(PrimitiveData{val});
+        self.r_hold.push(None);
+        self.mark(slot, VALUE);
+        self.prefix_vec.0.push(1);
+    }
 
+    fn push_hole(&mut self){
+        let slot = self.data.len();
+        self.data.push(PrimitiveData{rp: None});
+        self.r_hold.push(None);
+        self.mark(slot, HOLE);
+        self.prefix_vec.0.push(0);
+    }
 
+    //bits are worked out in u64, RawBuf only takes u32 offsets once they are known to be inside it
+    fn variant(&self, slot: usize) -> u8 {
+        let bit = slot as u64 * 2;
+        if bit + 2 > self.variant_marker.len as u64 {
+            return HOLE;
+        }
+        let bit = bit as u32;
+        (self.variant_marker.read_bit(bit) << 1) | self.variant_marker.read_bit(bit + 1)
+    }
+
+    fn mark(&mut self, slot: usize, variant: u8) {
+        let bit = slot as u64 * 2;
+        if bit + 2 > self.variant_marker.len as u64 {
+            //RawBuf counts its bits in a u32
+            let max_bytes = (u32::MAX / 8) as usize;
+            assert!(bit / 8 < max_bytes as u64, "more slots than the variant marker can tag");
+            let grow = self.variant_marker.data.len().max(1).min(max_bytes - self.variant_marker.data.len());
+            self.variant_marker.extend_by(grow);
+        }
+        self.variant_marker.write_bits(Bit(bit), variant as u64, 2, false);
+    }
+
+    //refills a hole left by drop_vec/take_fragment, None if the slot isn't a hole
+    pub fn insert_vec(&mut self, index: usize, other: Vec<T>) -> Option<FragmentId>{
+        if index >= self.data.len() { return None; }
+        self.insert_vec_inner(index, other)
+    }
+
+    //insert_vec without the bounds check
+    /// # Safety
+    ///
+    /// `index` has to be below the number of slots. Past the end the tag reads as a hole and the side table
+    /// lookup panics, later versions may index the slots without checking at all.
+    pub unsafe fn insert_vec_unchecked(&mut self, index: usize, other: Vec<T>) -> Option<FragmentId>{
+        self.insert_vec_inner(index, other)
+    }
+
+    fn insert_vec_inner(&mut self, slot: usize, other: Vec<T>) -> Option<FragmentId>{
+        if self.variant(slot) != HOLE {
+            return None;
+        }
+        let len = other.len();
+        let ptr = unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(other))) };
+        let id = self.next_id(slot);
+        self.r_hold[slot] = Some((ptr, len, id.generation()));
+        self.data[slot] = PrimitiveData{rp: Some(ptr)};
+        self.mark(slot, RP);
+        self.prefix_vec.0.set(slot, len);
+        self.rc += 1;
+        Some(id)
     }
 
     unsafe fn get_raw(&self, index: usize) -> Option<(&T, u8)> {
-        let target = index + 1;
-        let len = self.data.len();
-        let rough_index = self.prefix_vec.0.partition_point(|&x| x < target);
-        if rough_index >= len{
-            panic!("index out of bounds");
+        let (rough_index, offset) = self.prefix_vec.0.find(index)?;
+
+        match self.variant(rough_index) {
+            VALUE => Some((&self.data[rough_index].val, 1)),
+            RP => self.data[rough_index].rp.and_then(|p| p.as_ref().get(offset)).map(|v| (v, 0)),
+            _ => None,
         }
+    }
 
-        let data = &self.data[rough_index];
-        let ptr = match unsafe{(*data).rp}{
-            Some(ptr) => ptr,
-            None => panic!("Ts is none")
-        };
+}
+
+impl<T: Hash + Eq + Debug + Copy + Display, P: PrefixIndex> FragmentedVec<T> for PrimitiveMuted<T, P>{
+    type Iter<'a> = Iter<'a, T, P> where Self: 'a;
+
+    fn len(&self) -> usize {
+        PrimitiveMuted::len(self)
+    }
+    fn read(&self, index: usize) -> Option<&T> {
+        PrimitiveMuted::read(self, index)
+    }
+    fn write(&mut self, index: usize, val: T) -> Option<()> {
+        PrimitiveMuted::write(self, index, val)
+    }
+    fn push_vec(&mut self, other: Vec<T>) -> FragmentId {
+        PrimitiveMuted::push_vec(self, other)
+    }
+    fn drop_fragment(&mut self, id: FragmentId) -> Option<()> {
+        PrimitiveMuted::drop_fragment(self, id)
+    }
+    fn take_fragment(&mut self, at: impl Into<FragmentRef>) -> Option<Vec<T>> {
+        PrimitiveMuted::take_fragment(self, at)
+    }
+    fn insert_fragment(&mut self, slot: usize, other: Vec<T>) -> Option<FragmentId> {
+        PrimitiveMuted::insert_vec(self, slot, other)
+    }
+    fn fragment(&self, id: FragmentId) -> Option<&[T]> {
+        PrimitiveMuted::fragment(self, id)
+    }
+    fn fragment_mut(&mut self, id: FragmentId) -> Option<&mut [T]> {
+        PrimitiveMuted::fragment_mut(self, id)
+    }
+    fn fragment_range(&self, id: FragmentId) -> Option<Range<usize>> {
+        PrimitiveMuted::fragment_range(self, id)
+    }
+    fn iter(&self) -> Self::Iter<'_> {
+        PrimitiveMuted::iter(self)
+    }
+    fn compact(&mut self) {
+        PrimitiveMuted::compact(self)
+    }
+}
+
+//work is split along slot boundaries, same chunks as chunks()
+//one unit of work for par_chunks, a run of inline values still sits in the slots, a fragment is plain elements
+//opaque unlike generic::Chunk since the slots are a private union
+#[cfg(feature = "rayon")]
+pub struct Chunk<'a, T: Copy + Hash + Eq>(ChunkRepr<'a, T>);
+
+#[cfg(feature = "rayon")]
+enum ChunkRepr<'a, T: Copy + Hash + Eq> {
+    //only ever VALUE slots
+    Values(&'a [PrimitiveData<T>]),
+    Elements(&'a [T]),
+}
+
+//a chunk only reaches the val field of VALUE slots, which is a plain T, never a fragment pointer
+#[cfg(feature = "rayon")]
+unsafe impl<T: Copy + Hash + Eq + Sync> Send for Chunk<'_, T> {}
+#[cfg(feature = "rayon")]
+unsafe impl<T: Copy + Hash + Eq + Sync> Sync for Chunk<'_, T> {}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Copy + Hash + Eq> Chunk<'a, T> {
+    pub fn len(&self) -> usize {
+        match self.0 {
+            ChunkRepr::Values(values) => values.len(),
+            ChunkRepr::Elements(elements) => elements.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> ChunkIter<'a, T> {
+        match self.0 {
+            ChunkRepr::Values(values) => ChunkIter(ChunkIterRepr::Values(values.iter())),
+            ChunkRepr::Elements(elements) => ChunkIter(ChunkIterRepr::Elements(elements.iter())),
+        }
+    }
+}
 
-        match self.r_hold.get_key_value(&ptr) {
-            Some((_,v)) => {
-                return match v {
-                    Some(p) => {
-                        let vec: &Vec<PrimitiveData<T>> = &**p.0;
-                        let offset = if rough_index == 0{
-                            index
-                        }else {
-                            index - self.prefix_vec.0[rough_index - 1]
-                        };
-                        match vec.get(offset) {
-                            Some(PrimitiveData{val: v}) => Some((&v, 0)),
-                            None => None
+#[cfg(feature = "rayon")]
+pub struct ChunkIter<'a, T: Copy + Hash + Eq>(ChunkIterRepr<'a, T>);
+
+#[cfg(feature = "rayon")]
+enum ChunkIterRepr<'a, T: Copy + Hash + Eq> {
+    Values(slice::Iter<'a, PrimitiveData<T>>),
+    Elements(slice::Iter<'a, T>),
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Copy + Hash + Eq> Iterator for ChunkIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            ChunkIterRepr::Values(values) => values.next().map(|item| unsafe { &item.val }),
+            ChunkIterRepr::Elements(elements) => elements.next(),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+enum ChunkMut<'a, T: Copy + Hash + Eq> {
+    Values(slice::IterMut<'a, PrimitiveData<T>>),
+    Elements(slice::IterMut<'a, T>),
+}
+
+//same as Chunk, only the val field of VALUE slots is reached
+#[cfg(feature = "rayon")]
+unsafe impl<T: Copy + Hash + Eq + Send> Send for ChunkMut<'_, T> {}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Copy + Hash + Eq> Iterator for ChunkMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ChunkMut::Values(values) => values.next().map(|item| unsafe { &mut item.val }),
+            ChunkMut::Elements(elements) => elements.next(),
+        }
+    }
+}
+
+//work is split along slot boundaries the same way generic::Muted does it, a whole fragment or a run of inline values
+#[cfg(feature = "rayon")]
+impl<T: Hash + Eq + Debug + Copy + Display + Send + Sync, P: PrefixIndex> PrimitiveMuted<T, P>{
+    pub fn par_chunks(&self) -> impl IndexedParallelIterator<Item = Chunk<'_, T>> {
+        let mut chunks: Vec<Chunk<'_, T>> = Vec::new();
+        let mut run = 0;
+        for slot in 0..self.data.len() {
+            let variant = self.variant(slot);
+            if variant == VALUE {
+                continue;
+            }
+            if run < slot {
+                chunks.push(Chunk(ChunkRepr::Values(&self.data[run..slot])));
+            }
+            run = slot + 1;
+            if variant == RP {
+                if let Some(p) = unsafe { self.data[slot].rp } {
+                    chunks.push(Chunk(ChunkRepr::Elements(unsafe { p.as_ref() }.as_slice())));
+                }
+            }
+        }
+        if run < self.data.len() {
+            chunks.push(Chunk(ChunkRepr::Values(&self.data[run..])));
+        }
+        chunks.into_par_iter()
+    }
+
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = &T> {
+        self.par_chunks().flat_map_iter(|chunk| chunk.iter())
+    }
+
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut T> {
+        let variants: Vec<u8> = (0..self.data.len()).map(|slot| self.variant(slot)).collect();
+        let mut chunks: Vec<ChunkMut<'_, T>> = Vec::new();
+        let mut rest: &mut [PrimitiveData<T>] = &mut self.data[..];
+        let mut slot = 0;
+        while !rest.is_empty() {
+            let run = variants[slot..].iter().take_while(|&&v| v == VALUE).count();
+            let (values, tail) = rest.split_at_mut(run);
+            if !values.is_empty() {
+                chunks.push(ChunkMut::Values(values.iter_mut()));
+            }
+            slot += run;
+            match tail.split_first_mut() {
+                Some((item, tail)) => {
+                    //every fragment has its own box so the slices never overlap
+                    if variants[slot] == RP {
+                        if let Some(mut p) = unsafe { item.rp } {
+                            chunks.push(ChunkMut::Elements(unsafe { p.as_mut() }.iter_mut()));
                         }
-                    },
-                    None => None
+                    }
+                    slot += 1;
+                    rest = tail;
                 }
-            },
-            None => match self.data.get(rough_index) {
-                Some(PrimitiveData{val: v}) => Some((&v, 1)),
-                None => None
+                None => break,
+            }
+        }
+        chunks.into_par_iter().flat_map_iter(|chunk| chunk)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Hash + Eq + Debug + Copy + Display + Serialize, P: PrefixIndex> Serialize for PrimitiveMuted<T, P>{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+//the 32 bit flag isn't part of the data, it follows from T
+//a T new() would panic on (anything but i32/u32/i64/u64) is an error here instead
+#[cfg(feature = "serde")]
+impl<'de, T: Hash + Eq + Debug + Copy + Display + ToString + Deserialize<'de>, P: PrefixIndex> Deserialize<'de> for PrimitiveMuted<T, P>{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let is_32bit = std::mem::size_of::<T>() == 4;
+        if !Self::supported(is_32bit) {
+            return Err(serde::de::Error::custom(format!("Unsupported type: {}", std::any::type_name::<T>())));
+        }
+        let vec = Vec::<T>::deserialize(deserializer)?;
+        Ok(Self::new_with_index(vec, is_32bit))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Hash + Eq + Debug + Copy + Display + Serialize, P: PrefixIndex> Serialize for WithFragments<&PrimitiveMuted<T, P>>{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let muted = self.0;
+        serializer.collect_seq((0..muted.data.len()).map(|slot| match (muted.variant(slot), &muted.r_hold[slot]) {
+            (VALUE, _) => SlotRef::Val(unsafe { &muted.data[slot].val }),
+            (RP, Some(held)) => SlotRef::Fragment(unsafe { held.0.as_ref() }.as_slice()),
+            _ => SlotRef::<T, T>::Hole,
+        }))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Hash + Eq + Debug + Copy + Display + ToString + Deserialize<'de>, P: PrefixIndex> Deserialize<'de> for WithFragments<PrimitiveMuted<T, P>>{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let is_32bit = std::mem::size_of::<T>() == 4;
+        if !PrimitiveMuted::<T, P>::supported(is_32bit) {
+            return Err(serde::de::Error::custom(format!("Unsupported type: {}", std::any::type_name::<T>())));
+        }
+        let slots = Vec::<Slot<T>>::deserialize(deserializer)?;
+        let mut muted: PrimitiveMuted<T, P> = PrimitiveMuted::new_with_index(vec![], is_32bit);
+        for slot in slots {
+            match slot {
+                Slot::Val(val) => muted.push_inline(val),
+                Slot::Fragment(vec) => { muted.push_vec(vec); }
+                Slot::Hole => muted.push_hole(),
             }
         }
+        Ok(WithFragments(muted))
     }
+}
 
-}
\ No newline at end of file
+//native binary format, see binary.rs for the layout
+impl<T: Hash + Eq + Debug + Copy + Display + ToString + Pod, P: PrefixIndex> PrimitiveMuted<T, P>{
+    pub fn write_to(&self, out: impl Write) -> io::Result<()>{
+        let mut out = FormatWriter::new(out);
+        let header = Header{elem_size: std::mem::size_of::<T>(), slots: self.data.len(), len: self.len()};
+        out.bytes(&header.encode())?;
+        for slot in 0..self.data.len() {
+            let kind = match self.variant(slot) {
+                VALUE => SLOT_VAL,
+                RP => SLOT_FRAGMENT,
+                _ => SLOT_HOLE,
+            };
+            out.bytes(&encode_slot(kind, self.prefix_vec.0.prefix(slot), self.prefix_vec.0.get(slot)))?;
+        }
+        for slot in 0..self.data.len() {
+            match (self.variant(slot), &self.r_hold[slot]) {
+                (VALUE, _) => out.elements(slice::from_ref(unsafe { &self.data[slot].val }))?,
+                (RP, Some(held)) => out.elements(unsafe { held.0.as_ref() }.as_slice())?,
+                _ => (),
+            }
+        }
+        out.finish()
+    }
+
+    pub fn read_from(input: impl Read) -> io::Result<Self>{
+        let mut input = FormatReader::new(input);
+        let header = input.header::<T>()?;
+        let table = input.table(&header)?;
+        let mut muted: PrimitiveMuted<T, P> = PrimitiveMuted::new_with_index(vec![], std::mem::size_of::<T>() == 4);
+        let mut slot = 0;
+        while slot < table.len() {
+            match table[slot] {
+                //runs of inline values come in one read
+                (SLOT_VAL, _) => {
+                    let run = table[slot..].iter().take_while(|(kind, _)| *kind == SLOT_VAL).count();
+                    for val in input.elements::<T>(run)? {
+                        muted.push_inline(val);
+                    }
+                    slot += run;
+                    continue;
+                }
+                (SLOT_FRAGMENT, len) => { muted.push_vec(input.elements(len)?); }
+                _ => muted.push_hole(),
+            }
+            slot += 1;
+        }
+        input.finish()?;
+        Ok(muted)
+    }
+}
@@ -3,14 +3,32 @@ use std::marker::PhantomData;
 use core::default::Default;
 use std::{fmt, slice, vec};
 use std::any::{type_name, type_name_of_val, TypeId};
-
-#[derive(Debug, Eq, PartialEq)]
-pub struct RawBuf {
-    pub data: Vec<u8>,
+use crate::allocator::{vec_in, AVec, Allocator, Global};
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "AVec<u8, A>: serde::Serialize", deserialize = "AVec<u8, A>: serde::Deserialize<'de>")))]
+pub struct RawBuf<A: Allocator = Global> {
+    //crate only since its type names the private storage projection, bytes()/bytes_mut() are the way in from outside
+    pub(crate) data: AVec<u8, A>,
     pub last_index: u32,
     pub len: u32,
 }
 
+//by hand so the allocator doesn't have to be printable or comparable, std's Global isn't comparable
+impl<A: Allocator> Debug for RawBuf<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawBuf").field("data", &self.data).field("last_index", &self.last_index).field("len", &self.len).finish()
+    }
+}
+
+impl<A: Allocator> PartialEq for RawBuf<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data[..] == other.data[..] && self.last_index == other.last_index && self.len == other.len
+    }
+}
+impl<A: Allocator> Eq for RawBuf<A> {}
+
 
 #[derive(Debug)]
 pub enum BIT {
@@ -37,7 +55,7 @@ pub enum PrivateTypes {
     I8(i8),
 }
 
-impl Binary for RawBuf {
+impl<A: Allocator> Binary for RawBuf<A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut bytes: Vec<u8> = Vec::with_capacity((self.len * 8) as usize);
 
@@ -63,12 +81,12 @@ impl Binary for RawBuf {
     }
 }
 
-impl RawBuf {
+impl<A: Allocator> RawBuf<A> {
     pub fn read_bit(&self, bit_offset: u32) -> u8 {
         let offset = (bit_offset % 8) as u8;
         let byte_index = (bit_offset / 8) as usize;
         let bit_in_byte = 7 - offset;
-        return (self.data[byte_index] >> bit_in_byte) & 1;
+        (self.data[byte_index] >> bit_in_byte) & 1
     }
 
     pub fn write_bit(&mut self, bit_offset: offset, bit: BIT, append_mode: bool) {
@@ -118,7 +136,7 @@ impl RawBuf {
             "i8" => i8::from_str_radix(s.as_str(), 2).map(PrivateTypes::I8),
             _ => panic!("not supported type!")
         };
-        return Ok(t.unwrap())
+        Ok(t.unwrap())
     }
 
     pub fn write_bits(&mut self, bit_offset: offset, write: u64, bit_count: u32, append_mode: bool) {
@@ -147,13 +165,38 @@ impl RawBuf {
 
     }
     
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    //sets the first count 2 bit fields to tag a whole byte at a time, how the variant markers start out
+    pub(crate) fn fill_pairs(&mut self, count: usize, tag: u8) {
+        self.data[..count / 4].fill(tag * 0b0101_0101);
+        for i in count / 4 * 4..count {
+            self.write_bits(offset::Bit(i as u64 * 2), tag as u64, 2, false);
+        }
+    }
+
     pub fn extend_by(&mut self, bytes: usize) {
         let new_size = self.data.len() + bytes;
         self.data.resize(new_size, 0);
+        self.len = (new_size * 8) as u32;
     }
 
-    pub fn new<'a>(byte_size: u32) -> RawBuf{
-        return RawBuf{data: vec![0; byte_size as usize], len: byte_size * 8, last_index: 0};
+    pub fn new_in(byte_size: u32, alloc: A) -> Self{
+        let mut data = vec_in(byte_size as usize, alloc);
+        data.resize(byte_size as usize, 0);
+        RawBuf{data, len: byte_size * 8, last_index: 0}
     }
 
 }
+
+impl RawBuf {
+    pub fn new<'a>(byte_size: u32) -> RawBuf{
+        RawBuf::new_in(byte_size, Global)
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+//by default every container serializes as a plain sequence of its elements and comes back with everything inline
+//wrap it in WithFragments to keep the slot layout, so fragments, inline values and holes all come back where they were
+//  serde_json::to_string(&WithFragments(&muted))
+//  let muted: WithFragments<Muted<u64>> = serde_json::from_str(&json)?;
+pub struct WithFragments<M>(pub M);
+
+impl<M> WithFragments<M> {
+    pub fn into_inner(self) -> M {
+        self.0
+    }
+}
+
+//one entry per slot, same order as the prefix index
+#[derive(Debug, Deserialize)]
+pub(crate) enum Slot<T> {
+    Val(T),
+    Fragment(Vec<T>),
+    Hole,
+}
+
+//borrowed twin of Slot so serializing doesn't have to copy fragments out
+//E is whatever the fragment stores, it has to serialize as a plain T
+#[derive(Serialize)]
+#[serde(rename = "Slot")]
+pub(crate) enum SlotRef<'a, T, E> {
+    Val(&'a T),
+    Fragment(&'a [E]),
+    Hole,
+}
@@ -0,0 +1,169 @@
+use muted::{FragmentId, Muted};
+mod common;
+use common::next;
+
+//every slot of the muted as a plain vec, None for holes, with the id that was handed out for it
+struct Model {
+    slots: Vec<Option<Vec<u64>>>,
+    ids: Vec<Option<FragmentId>>,
+}
+
+impl Model {
+    fn flat(&self) -> Vec<u64> {
+        self.slots.iter().flatten().flatten().copied().collect()
+    }
+
+    //whole slots starting at or past len go, the one len lands in is shortened, nothing changes if len isn't shorter
+    fn truncate(&mut self, len: usize) {
+        if len >= self.flat().len() {
+            return;
+        }
+        let mut start = 0;
+        let mut keep = 0;
+        for (slot, vec) in self.slots.iter_mut().enumerate() {
+            if start >= len {
+                break;
+            }
+            let vec_len = vec.as_ref().map_or(0, Vec::len);
+            if let Some(vec) = vec {
+                vec.truncate(len - start);
+            }
+            start += vec_len;
+            keep = slot + 1;
+        }
+        self.slots.truncate(keep);
+        self.ids.truncate(keep);
+    }
+
+    //with nothing left to pop the holes stay
+    fn pop(&mut self) -> Option<u64> {
+        if self.flat().is_empty() {
+            return None;
+        }
+        while self.slots.last().is_some_and(|vec| vec.as_ref().is_none_or(Vec::is_empty)) {
+            self.slots.pop();
+            self.ids.pop();
+        }
+        let last = self.slots.last_mut()?.as_mut().unwrap();
+        let v = last.pop();
+        if last.is_empty() {
+            self.slots.pop();
+            self.ids.pop();
+        }
+        v
+    }
+
+    fn check(&self, muted: &Muted<u64>) {
+        let flat = self.flat();
+        assert_eq!(muted.len(), flat.len());
+        assert!(muted.iter().eq(flat.iter()));
+        for (index, val) in flat.iter().enumerate() {
+            assert_eq!(muted.read(index), Some(val));
+        }
+        assert_eq!(muted.read(flat.len()), None);
+        let mut start = 0;
+        for (vec, id) in self.slots.iter().zip(&self.ids) {
+            let id = id.expect("every slot in the model came from push_vec or insert_vec");
+            match vec {
+                Some(vec) => {
+                    assert_eq!(muted.fragment(id), Some(&vec[..]));
+                    assert_eq!(muted.fragment_range(id), Some(start..start + vec.len()));
+                    start += vec.len();
+                }
+                None => assert_eq!(muted.fragment(id), None),
+            }
+        }
+    }
+}
+
+//random pushes, drops, refills, writes, truncates and pops with the arena switched on and off in between
+//enough fragments go in that the slab has to move more than once, the model is checked after every step
+#[test]
+fn arena_follows_a_plain_vec() {
+    let mut state = 0x2545f4914f6cdd1d;
+    let mut muted: Muted<u64> = Muted::new(vec![]);
+    muted.set_arena(true);
+    let mut model = Model { slots: Vec::new(), ids: Vec::new() };
+    let steps = if cfg!(miri) { 150 } else { 3000 };
+    for step in 0..steps as u64 {
+        match next(&mut state) % 12 {
+            0..=3 => {
+                let len = next(&mut state) % 6;
+                let vec: Vec<u64> = (step * 10..step * 10 + len).collect();
+                model.ids.push(Some(muted.push_vec(vec.clone())));
+                model.slots.push(Some(vec));
+            }
+            4 => {
+                //push grows the last fragment in place, the arena only has to update its len
+                if let Some(Some(vec)) = model.slots.last_mut() {
+                    muted.push(step);
+                    vec.push(step);
+                }
+            }
+            5 => if !model.slots.is_empty() {
+                let slot = (next(&mut state) % model.slots.len() as u64) as usize;
+                let id = model.ids[slot].unwrap();
+                assert_eq!(muted.drop_fragment(id).is_some(), model.slots[slot].take().is_some());
+            },
+            6 => if let Some(slot) = model.slots.iter().position(Option::is_none) {
+                //reuses the slab entry the drop freed
+                let id = muted.insert_vec(slot, vec![step; 3]).unwrap();
+                assert_eq!(muted.fragment(model.ids[slot].unwrap()), None);
+                model.ids[slot] = Some(id);
+                model.slots[slot] = Some(vec![step; 3]);
+            },
+            7 | 8 => if !muted.is_empty() {
+                let index = (next(&mut state) % muted.len() as u64) as usize;
+                muted.write(index, step).unwrap();
+                let mut left = index;
+                for vec in model.slots.iter_mut().flatten() {
+                    if left < vec.len() {
+                        vec[left] = step;
+                        break;
+                    }
+                    left -= vec.len();
+                }
+            },
+            9 => {
+                let len = (next(&mut state) % (muted.len() as u64 + 1)) as usize;
+                muted.truncate(len);
+                model.truncate(len);
+            }
+            10 => assert_eq!(muted.pop(), model.pop()),
+            _ => {
+                muted.set_arena(!muted.is_arena());
+                if step % 2 == 0 {
+                    muted.shrink_to_fit();
+                }
+            }
+        }
+        model.check(&muted);
+    }
+}
+
+//a clone of an arena copies every fragment, changes on either side don't show on the other
+#[test]
+fn arena_clone_is_independent() {
+    let count = if cfg!(miri) { 40 } else { 1000 };
+    let mut muted: Muted<u64> = Muted::new(vec![]);
+    muted.set_arena(true);
+    let ids: Vec<FragmentId> = (0..count).map(|f| muted.push_vec(vec![f, f + 1])).collect();
+    let flat: Vec<u64> = (0..count).flat_map(|f| [f, f + 1]).collect();
+
+    let mut copy = muted.clone();
+    assert!(copy.is_arena());
+    muted.write(0, 100).unwrap();
+    muted.drop_fragment(ids[1]).unwrap();
+    muted.push(7);
+    muted.truncate(10);
+    assert!(copy.iter().eq(flat.iter()));
+    assert_eq!(copy.fragment(ids[1]), Some(&[1, 2][..]));
+
+    copy.write(1, 50).unwrap();
+    assert_eq!(copy.pop(), Some(count));
+    copy.set_arena(false);
+    assert_eq!(muted.iter().copied().collect::<Vec<_>>(), vec![100, 1, 2, 3, 3, 4, 4, 5, 5, 6]);
+    assert_eq!(copy.read(1), Some(&50));
+    assert_eq!(copy.len(), flat.len() - 1);
+    assert_eq!(muted.fragment(ids[0]), Some(&[100, 1][..]));
+}
@@ -0,0 +1,62 @@
+use std::io::{Cursor, ErrorKind};
+use muted::binary::{FORMAT_VERSION, MAGIC, SLOT_FRAGMENT};
+use muted::{Muted as Generic, PrimitiveMuted};
+
+//header plus slot table for u64 elements, nothing after it
+fn table(len: u64, slots: &[(u64, u64, u64)]) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    out.extend_from_slice(&8u16.to_le_bytes());
+    out.extend_from_slice(&(if cfg!(target_endian = "big") { 1u32 } else { 0 }).to_le_bytes());
+    out.extend_from_slice(&(slots.len() as u64).to_le_bytes());
+    out.extend_from_slice(&len.to_le_bytes());
+    for (kind, offset, len) in slots {
+        out.extend_from_slice(&kind.to_le_bytes());
+        out.extend_from_slice(&offset.to_le_bytes());
+        out.extend_from_slice(&len.to_le_bytes());
+    }
+    out
+}
+
+//a fragment claiming 8 TiB runs out of input instead of allocating all of it up front
+#[test]
+fn huge_fragment_len_is_not_allocated() {
+    let bytes = table(1 << 40, &[(SLOT_FRAGMENT, 0, 1 << 40)]);
+    let err = Generic::<u64>::read_from(Cursor::new(&bytes)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    let err = PrimitiveMuted::<u64>::read_from(Cursor::new(&bytes)).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn overflowing_slot_lengths_are_rejected() {
+    let bytes = table(1, &[(SLOT_FRAGMENT, 0, u64::MAX), (SLOT_FRAGMENT, u64::MAX, 2)]);
+    let err = Generic::<u64>::read_from(Cursor::new(&bytes)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = PrimitiveMuted::<u64>::read_from(Cursor::new(&bytes)).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn round_trip_across_chunks() {
+    let mut muted: Generic<u64> = Generic::new(vec![1, 2]);
+    muted.push_vec((0..100_000).collect());
+    let mut bytes = Vec::new();
+    muted.write_to(&mut bytes).unwrap();
+    let back = Generic::<u64>::read_from(Cursor::new(&bytes)).unwrap();
+    assert!(back.iter().eq(muted.iter()));
+}
+
+#[cfg(feature = "memmap2")]
+#[test]
+fn mapped_rejects_overflowing_slot_lengths() {
+    use muted::MappedMuted;
+    let mut bytes = table(1, &[(SLOT_FRAGMENT, 0, u64::MAX), (SLOT_FRAGMENT, u64::MAX, 2)]);
+    bytes.extend_from_slice(&[0; 16]);
+    let path = std::env::temp_dir().join(format!("muted-overflow-{}.bin", std::process::id()));
+    std::fs::write(&path, &bytes).unwrap();
+    //nothing else knows about the file, it doesn't change while mapped
+    let err = unsafe { MappedMuted::<u64>::open(&path) }.err().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
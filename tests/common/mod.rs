@@ -0,0 +1,16 @@
+//helpers shared by the integration tests, each test file only uses some of them
+#![allow(dead_code)]
+use muted::{Muted as Generic, PrimitiveMuted, UnionMuted};
+
+//small xorshift so the walk order is mixed but the same on every run
+pub fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+//the same starting values in all three containers, for checks that run on each of them
+pub fn backends(start: Vec<u64>) -> (Generic<u64>, PrimitiveMuted<u64>, UnionMuted<u64>) {
+    (Generic::new(start.clone()), PrimitiveMuted::new(start.clone(), false), UnionMuted::new(start))
+}
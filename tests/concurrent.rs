@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use muted::{ConcurrentMuted, ConcurrentSnapshot};
+
+const PRODUCERS: u64 = 4;
+const FRAGMENTS: u64 = 200;
+
+fn collect(snapshot: &ConcurrentSnapshot<u64>) -> Vec<u64> {
+    snapshot.iter().copied().collect()
+}
+
+//every value is producer * 1_000_000 + its own running count, so order and duplicates can be checked afterwards
+#[test]
+fn producers_publish_everything_once_and_in_order() {
+    let concurrent: ConcurrentMuted<u64> = ConcurrentMuted::with_shards(3);
+    let done = AtomicBool::new(false);
+    let older = thread::scope(|scope| {
+        let mut producers = Vec::new();
+        for producer in 0..PRODUCERS {
+            let concurrent = &concurrent;
+            producers.push(scope.spawn(move || {
+                let mut count = 0;
+                for fragment in 0..FRAGMENTS {
+                    let len = fragment % 4 + 1;
+                    concurrent.push_vec((count..count + len).map(|n| producer * 1_000_000 + n).collect());
+                    count += len;
+                }
+            }));
+        }
+        //publishing while the producers are still going, every older snapshot has to stay as it was
+        let publisher = scope.spawn(|| {
+            let mut older: Vec<(ConcurrentSnapshot<u64>, Vec<u64>)> = Vec::new();
+            while !done.load(Ordering::Acquire) {
+                let snapshot = concurrent.publish();
+                let seen = collect(&snapshot);
+                for (old, seen) in older.iter() {
+                    assert_eq!(&collect(old), seen);
+                    assert!(old.len() <= snapshot.len());
+                }
+                older.push((snapshot, seen));
+                thread::yield_now();
+            }
+            older
+        });
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        done.store(true, Ordering::Release);
+        publisher.join().unwrap()
+    });
+
+    let last = concurrent.publish();
+    for (old, seen) in older.iter() {
+        assert_eq!(&collect(old), seen);
+        assert_eq!(last.iter().take(old.len()).copied().collect::<Vec<_>>(), *seen);
+    }
+
+    let all = collect(&last);
+    assert_eq!(all.len(), last.len());
+    let mut next = vec![0; PRODUCERS as usize];
+    for val in all.iter() {
+        let producer = (val / 1_000_000) as usize;
+        assert_eq!(val % 1_000_000, next[producer], "producer {} out of order", producer);
+        next[producer] += 1;
+    }
+    //1 + 2 + 3 + 4 repeated over the fragments
+    let per_producer = (0..FRAGMENTS).map(|fragment| fragment % 4 + 1).sum::<u64>();
+    assert_eq!(next, vec![per_producer; PRODUCERS as usize]);
+    for (index, val) in all.iter().enumerate() {
+        assert_eq!(last.read(index), Some(val));
+    }
+    assert_eq!(last.read(all.len()), None);
+
+    //nothing pending, publishing again changes nothing
+    assert_eq!(concurrent.publish().len(), last.len());
+    assert_eq!(concurrent.snapshot().len(), last.len());
+}
+
+#[test]
+fn snapshots_are_send_and_sync() {
+    fn shareable<S: Send + Sync>(_: &S) {}
+    let concurrent: ConcurrentMuted<u64> = ConcurrentMuted::with_shards(1);
+    concurrent.push_vec(vec![1, 2]);
+    let snapshot = concurrent.publish();
+    shareable(&concurrent);
+    shareable(&snapshot);
+    let read = thread::spawn(move || snapshot.iter().sum::<u64>()).join().unwrap();
+    assert_eq!(read, 3);
+}
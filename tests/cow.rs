@@ -0,0 +1,84 @@
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use muted::{FragmentId, Muted};
+
+type Change = fn(&mut Muted<u64>, &[FragmentId]);
+
+//two inline values then three fragments of 4
+fn build() -> (Muted<u64>, Vec<FragmentId>) {
+    let mut muted = Muted::new(vec![0, 1]);
+    let ids = (1..4).map(|f| muted.push_vec((f * 10..f * 10 + 4).collect())).collect();
+    (muted, ids)
+}
+
+fn starts(muted: &Muted<u64>, ids: &[FragmentId]) -> Vec<*const u64> {
+    ids.iter().map(|id| muted.fragment(*id).unwrap().as_ptr()).collect()
+}
+
+//a change made on one side of a clone shows on that side only, the other keeps its elements and buffers
+//fragments the change didn't touch (the first one, if first_untouched) stay shared between the two
+fn stays_on_its_side(name: &str, change: Change, first_untouched: bool) {
+    for change_clone in [false, true] {
+        let (mut muted, ids) = build();
+        let mut copy = muted.clone();
+        let shared = starts(&muted, &ids);
+        assert_eq!(starts(&copy, &ids), shared, "{name}");
+        let before: Vec<u64> = muted.iter().copied().collect();
+
+        let (changed, kept) = if change_clone { (&mut copy, &muted) } else { (&mut muted, &copy) };
+        change(changed, &ids);
+        //the same change on a muted that was never cloned
+        let (mut alone, _) = build();
+        change(&mut alone, &ids);
+
+        assert!(changed.iter().eq(alone.iter()), "{name}");
+        assert!(kept.iter().eq(before.iter()), "{name}");
+        assert_eq!(starts(kept, &ids), shared, "{name}");
+        if first_untouched {
+            assert_eq!(changed.fragment(ids[0]).unwrap().as_ptr(), shared[0], "{name}");
+        }
+    }
+}
+
+#[test]
+fn changes_stay_on_their_side_of_a_clone() {
+    let changes: [(&str, Change); 6] = [
+        ("write", |m, _| m.write(7, 99).unwrap()),
+        ("fragment_mut", |m, ids| m.fragment_mut(ids[1]).unwrap()[0] = 99),
+        ("push", |m, _| m.push(99)),
+        ("truncate", |m, _| m.truncate(12)),
+        ("pop", |m, _| assert_eq!(m.pop(), Some(33))),
+        ("take_fragment", |m, ids| assert_eq!(m.take_fragment(ids[2]), Some(vec![30, 31, 32, 33]))),
+    ];
+    for (name, change) in changes {
+        stays_on_its_side(name, change, true);
+    }
+}
+
+//visits every fragment so every one of them gets copied
+#[cfg(feature = "rayon")]
+#[test]
+fn par_iter_mut_stays_on_its_side() {
+    stays_on_its_side("par_iter_mut", |m, _| m.par_iter_mut().for_each(|v| *v += 1), false);
+}
+
+//once a write copied the fragment the side that made it owns its copy, further writes don't copy again
+#[test]
+fn a_copied_fragment_is_only_copied_once() {
+    let (mut muted, ids) = build();
+    let copy = muted.clone();
+    muted.write(2, 5).unwrap();
+    let copied = muted.fragment(ids[0]).unwrap().as_ptr();
+    assert_ne!(copied, copy.fragment(ids[0]).unwrap().as_ptr());
+    muted.write(3, 6).unwrap();
+    muted.fragment_mut(ids[0]).unwrap()[2] = 7;
+    assert_eq!(muted.fragment(ids[0]).unwrap().as_ptr(), copied);
+    assert_eq!(muted.fragment(ids[0]), Some(&[5, 6, 7, 13][..]));
+    assert_eq!(copy.fragment(ids[0]), Some(&[10, 11, 12, 13][..]));
+
+    //dropping the other side leaves the last fragment unshared, a push doesn't have to copy it
+    let last = muted.fragment(ids[2]).unwrap().as_ptr();
+    drop(copy);
+    muted.write(13, 8).unwrap();
+    assert_eq!(muted.fragment(ids[2]).unwrap().as_ptr(), last);
+}
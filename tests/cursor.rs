@@ -0,0 +1,117 @@
+use std::thread;
+use muted::Muted as Generic;
+mod common;
+use common::next;
+
+fn same_reads(cached: &Generic<u64>, plain: &Generic<u64>, state: &mut u64) {
+    assert_eq!(cached.len(), plain.len());
+    //walking back from the end first lands in whatever the cursor was left on before the last change
+    for index in (0..plain.len()).rev() {
+        assert_eq!(cached.read(index), plain.read(index));
+    }
+    for _ in 0..plain.len() {
+        let index = (next(state) % plain.len() as u64) as usize;
+        assert_eq!(cached.read(index), plain.read(index));
+    }
+    //forward walks hit the cursor and the slot after it, and leave it on the last slot for the next change
+    for index in 0..plain.len() {
+        assert_eq!(cached.read(index), plain.read(index));
+    }
+}
+
+#[test]
+fn cached_lookups_match_uncached() {
+    let mut state = 0x9e3779b97f4a7c15;
+    let mut cached: Generic<u64> = Generic::new((0..5).collect());
+    cached.set_cursor_cache(true);
+    let mut plain: Generic<u64> = Generic::new((0..5).collect());
+    let mut ids = Vec::new();
+    for len in [3, 0, 7, 1, 4, 9, 2] {
+        let vec: Vec<u64> = (100 * len..100 * len + len).collect();
+        ids.push((cached.push_vec(vec.clone()), plain.push_vec(vec)));
+    }
+    same_reads(&cached, &plain, &mut state);
+
+    //every change to the layout has to throw the cursor away
+    for (round, (cached_id, plain_id)) in ids.into_iter().enumerate() {
+        let slot = cached_id.slot();
+        cached.drop_fragment(cached_id).unwrap();
+        plain.drop_fragment(plain_id).unwrap();
+        same_reads(&cached, &plain, &mut state);
+
+        let vec: Vec<u64> = (1000 * round as u64..1000 * round as u64 + round as u64 * 2).collect();
+        cached.insert_vec(slot, vec.clone()).unwrap();
+        plain.insert_vec(slot, vec).unwrap();
+        same_reads(&cached, &plain, &mut state);
+
+        cached.push(round as u64);
+        plain.push(round as u64);
+        cached.take_fragment(slot).unwrap();
+        plain.take_fragment(slot).unwrap();
+        same_reads(&cached, &plain, &mut state);
+    }
+
+    cached.truncate(cached.len() / 2);
+    plain.truncate(plain.len() / 2);
+    cached.pop();
+    plain.pop();
+    same_reads(&cached, &plain, &mut state);
+}
+
+//threads racing on the cursor can make each other skip it but never read the wrong element
+#[test]
+fn cached_lookups_from_several_threads() {
+    let mut cached: Generic<u64> = Generic::new((0..10).collect());
+    cached.set_cursor_cache(true);
+    for start in (10..1000).step_by(10) {
+        cached.push_vec((start..start + 10).collect());
+    }
+    thread::scope(|scope| {
+        for seed in 1..5u64 {
+            let cached = &cached;
+            scope.spawn(move || {
+                let mut state = seed * 0x2545f4914f6cdd1d;
+                for _ in 0..10_000 {
+                    let index = (next(&mut state) % 1000) as usize;
+                    assert_eq!(cached.read(index), Some(&(index as u64)));
+                    assert_eq!(cached.read((index + 1) % 1000), Some(&((index as u64 + 1) % 1000)));
+                }
+            });
+        }
+    });
+}
+
+//push grows the tail fragment in place, the cursor can't keep the old end of it
+#[test]
+fn cached_lookups_across_push() {
+    let mut state = 0x2545f4914f6cdd1d;
+    let mut cached: Generic<u64> = Generic::new(vec![]);
+    cached.set_cursor_cache(true);
+    let mut plain: Generic<u64> = Generic::new(vec![]);
+    for round in 0..50u64 {
+        match next(&mut state) % 3 {
+            0 => {
+                let vec: Vec<u64> = (round * 100..round * 100 + round % 4).collect();
+                cached.push_vec(vec.clone());
+                plain.push_vec(vec);
+            }
+            _ => {
+                cached.push(round);
+                plain.push(round);
+            }
+        }
+        if !plain.is_empty() {
+            let index = (next(&mut state) % plain.len() as u64) as usize;
+            assert_eq!(cached.read(index), plain.read(index));
+        }
+        same_reads(&cached, &plain, &mut state);
+    }
+
+    let mut cached: Generic<u64> = Generic::new(vec![]);
+    cached.set_cursor_cache(true);
+    cached.push_vec(vec![1, 2]);
+    assert_eq!(cached.read(1), Some(&2));
+    cached.push(3);
+    cached.push_vec(vec![10, 20]);
+    assert_eq!(cached.read(2), Some(&3));
+}
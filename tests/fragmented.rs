@@ -0,0 +1,221 @@
+use muted::{FragmentedVec, Muted as Generic, PrimitiveMuted};
+
+mod common;
+use common::backends;
+
+//every check runs on all three containers through the shared trait
+macro_rules! on_all {
+    ($start:expr, $check:ident) => {{
+        let (generic, primitive, union) = backends($start);
+        $check(generic);
+        $check(primitive);
+        $check(union);
+    }};
+}
+
+fn collect<V: FragmentedVec<u64>>(muted: &V) -> Vec<u64> {
+    muted.iter().copied().collect()
+}
+
+fn len_follows_drop_and_insert<V: FragmentedVec<u64>>(mut muted: V) {
+    let dropped = muted.push_vec((3..8).collect());
+    muted.push_vec(vec![8, 9, 10]);
+    assert_eq!(muted.len(), 11);
+
+    muted.drop_fragment(dropped).unwrap();
+    assert_eq!(muted.len(), 6);
+    assert_eq!(muted.len(), muted.iter().count());
+    assert_eq!(muted.read(5), Some(&10));
+
+    muted.insert_fragment(dropped.slot(), vec![20, 21, 22, 23]).unwrap();
+    assert_eq!(muted.len(), 10);
+    assert_eq!(muted.len(), muted.iter().count());
+    assert_eq!(collect(&muted), vec![0, 1, 2, 20, 21, 22, 23, 8, 9, 10]);
+    for (index, val) in collect(&muted).iter().enumerate() {
+        assert_eq!(muted.read(index), Some(val));
+    }
+
+    muted.take_fragment(dropped.slot()).unwrap();
+    assert_eq!(muted.len(), 6);
+    assert_eq!(muted.len(), muted.iter().count());
+}
+
+#[test]
+fn len_matches_iter() {
+    on_all!(vec![0, 1, 2], len_follows_drop_and_insert);
+}
+
+fn ranges_follow_drop_and_insert<V: FragmentedVec<u64>>(mut muted: V) {
+    let first = muted.push_vec(vec![3, 4, 5]);
+    let second = muted.push_vec(vec![6, 7]);
+    assert_eq!(muted.fragment_range(first), Some(3..6));
+    assert_eq!(muted.fragment_range(second), Some(6..8));
+
+    muted.drop_fragment(first).unwrap();
+    assert_eq!(muted.fragment_range(first), None);
+    assert_eq!(muted.fragment_range(second), Some(3..5));
+
+    let refilled = muted.insert_fragment(first.slot(), vec![9; 4]).unwrap();
+    assert_eq!(muted.fragment_range(refilled), Some(3..7));
+    assert_eq!(muted.fragment_range(second), Some(7..9));
+    let range = muted.fragment_range(second).unwrap();
+    assert_eq!(range.map(|index| *muted.read(index).unwrap()).collect::<Vec<_>>(), vec![6, 7]);
+}
+
+#[test]
+fn fragment_range_after_drop_and_insert() {
+    on_all!(vec![0, 1, 2], ranges_follow_drop_and_insert);
+}
+
+fn empty_until_something_is_pushed<V: FragmentedVec<u64>>(mut muted: V) {
+    assert!(muted.is_empty());
+    assert_eq!(muted.iter().next(), None);
+    let empty = muted.push_vec(vec![]);
+    assert!(muted.is_empty());
+    assert_eq!(muted.fragment_range(empty), Some(0..0));
+    let id = muted.push_vec(vec![1, 2]);
+    assert!(!muted.is_empty());
+    assert_eq!(muted.len(), 2);
+    muted.drop_fragment(id).unwrap();
+    assert!(muted.is_empty());
+    assert_eq!(muted.len(), 0);
+}
+
+#[test]
+fn is_empty_follows_len() {
+    on_all!(vec![], empty_until_something_is_pushed);
+}
+
+fn stale_ids_are_rejected<V: FragmentedVec<u64>>(mut muted: V) {
+    let id = muted.push_vec(vec![3, 4]);
+    let other = muted.push_vec(vec![5]);
+    assert_ne!(id, other);
+    muted.drop_fragment(id).unwrap();
+    assert_eq!(muted.drop_fragment(id), None);
+    assert_eq!(muted.fragment_range(id), None);
+
+    //a refill reuses the slot but not the id, the old one keeps failing
+    let refill = muted.insert_fragment(id.slot(), vec![6, 7, 8]).unwrap();
+    assert_eq!(refill.slot(), id.slot());
+    assert_ne!(refill, id);
+    assert_eq!(muted.fragment_range(id), None);
+    assert_eq!(muted.drop_fragment(id), None);
+    assert_eq!(muted.take_fragment(id), None);
+    assert_eq!(muted.fragment_range(refill), Some(1..4));
+    assert_eq!(collect(&muted), vec![0, 6, 7, 8, 5]);
+
+    //only holes can be refilled
+    assert_eq!(muted.insert_fragment(refill.slot(), vec![9]), None);
+    assert_eq!(muted.insert_fragment(0, vec![9]), None);
+    assert_eq!(muted.insert_fragment(100, vec![9]), None);
+
+    muted.drop_fragment(refill).unwrap();
+    muted.drop_fragment(other).unwrap();
+    assert_eq!(collect(&muted), vec![0]);
+    assert_eq!(muted.fragment_range(other), None);
+}
+
+#[test]
+fn handles_stop_resolving_once_dropped() {
+    on_all!(vec![0], stale_ids_are_rejected);
+}
+
+fn take_by_id_or_slot<V: FragmentedVec<u64>>(mut muted: V) {
+    let by_id = muted.push_vec(vec![3, 4, 5]);
+    let by_slot = muted.push_vec(vec![6, 7]);
+    assert_eq!(muted.take_fragment(by_id), Some(vec![3, 4, 5]));
+    assert_eq!(muted.take_fragment(by_slot.slot()), Some(vec![6, 7]));
+    assert_eq!(collect(&muted), vec![0, 1, 2]);
+
+    //nothing left to take, neither through the id nor through the hole it left
+    assert_eq!(muted.take_fragment(by_id), None);
+    assert_eq!(muted.take_fragment(by_id.slot()), None);
+    assert_eq!(muted.take_fragment(by_slot), None);
+    //inline values aren't fragments
+    assert_eq!(muted.take_fragment(0usize), None);
+
+    let refill = muted.insert_fragment(by_id.slot(), vec![8]).unwrap();
+    assert_eq!(muted.take_fragment(by_id), None);
+    assert_eq!(muted.take_fragment(refill), Some(vec![8]));
+    assert_eq!(muted.len(), 3);
+}
+
+#[test]
+fn take_fragment_hands_the_elements_back() {
+    on_all!(vec![0, 1, 2], take_by_id_or_slot);
+}
+
+//below the threshold push_vec inlines, the id it hands back is fresh but there is no fragment behind it
+fn inline_ids_never_resolve<V: FragmentedVec<u64>>(mut muted: V) {
+    let inlined = muted.push_vec(vec![1, 2]);
+    let fragment = muted.push_vec(vec![3, 4, 5, 6]);
+    assert_eq!(collect(&muted), vec![0, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(muted.fragment_range(inlined), None);
+    assert_eq!(muted.drop_fragment(inlined), None);
+    assert_eq!(muted.take_fragment(inlined), None);
+    assert_eq!(muted.fragment_range(fragment), Some(3..7));
+
+    //ids handed out later never collide with it
+    muted.drop_fragment(fragment).unwrap();
+    let refill = muted.insert_fragment(fragment.slot(), vec![7; 4]).unwrap();
+    assert_ne!(refill, inlined);
+    assert_eq!(muted.fragment_range(inlined), None);
+    assert_eq!(collect(&muted), vec![0, 1, 2, 7, 7, 7, 7]);
+}
+
+#[test]
+fn inlined_push_vec_ids() {
+    inline_ids_never_resolve(Generic::new(vec![0]).with_inline_threshold(4));
+    inline_ids_never_resolve(PrimitiveMuted::new(vec![0], false).with_inline_threshold(4));
+}
+
+fn none_past_the_end<V: FragmentedVec<u64>>(mut muted: V) {
+    assert_eq!(muted.read(0), None);
+    assert_eq!(muted.write(0, 1), None);
+    muted.push_vec(vec![1, 2, 3]);
+    assert_eq!(muted.read(2), Some(&3));
+    assert_eq!(muted.read(3), None);
+    assert_eq!(muted.write(3, 4), None);
+    assert_eq!(muted.read(usize::MAX), None);
+    assert_eq!(collect(&muted), vec![1, 2, 3]);
+}
+
+#[test]
+fn out_of_range_reads_and_writes_give_none() {
+    on_all!(vec![], none_past_the_end);
+}
+
+//compacting nothing (or only holes) leaves no fragment behind
+#[test]
+fn compact_without_elements() {
+    let (mut generic, mut primitive, mut union) = backends(vec![]);
+    generic.compact();
+    primitive.compact();
+    FragmentedVec::compact(&mut union);
+    assert_eq!((generic.fragment_count(), primitive.fragment_count(), union.fragment_count()), (0, 0, 0));
+    assert_eq!((generic.stats().fragments, primitive.stats().fragments, union.stats().fragments), (0, 0, 0));
+
+    let id = generic.push_vec(vec![1, 2]);
+    generic.drop_fragment(id).unwrap();
+    generic.compact();
+    assert_eq!(generic.stats().fragments, 0);
+    assert_eq!(generic.stats().slots, 0);
+    assert!(generic.is_empty());
+}
+
+fn fragments_are_plain_slices<V: FragmentedVec<u64>>(mut muted: V) {
+    let id = muted.push_vec(vec![3, 4, 5]);
+    assert_eq!(muted.fragment(id), Some(&[3, 4, 5][..]));
+    muted.fragment_mut(id).unwrap()[1] = 40;
+    assert_eq!(muted.read(4), Some(&40));
+    assert_eq!(collect(&muted), vec![0, 1, 2, 3, 40, 5]);
+
+    muted.drop_fragment(id).unwrap();
+    assert_eq!(muted.fragment(id), None);
+    assert_eq!(muted.fragment_mut(id), None);
+}
+
+#[test]
+fn fragment_hands_out_elements() {
+    on_all!(vec![0, 1, 2], fragments_are_plain_slices);
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "rayon")]
+use rayon::prelude::*;
+use muted::{FragmentedVec, Muted as Generic, PrimitiveMuted};
+mod common;
+use common::backends;
+
+//a run of inline values is one chunk, every fragment is one more
+#[test]
+fn chunks_group_inline_runs() {
+    let (mut generic, mut primitive, mut union) = backends((0..6).collect());
+    let generic_id = generic.push_vec(vec![6, 7, 8]);
+    let primitive_id = primitive.push_vec(vec![6, 7, 8]);
+    FragmentedVec::push_vec(&mut union, vec![6, 7, 8]);
+    generic.write(2, 20).unwrap();
+    primitive.write(2, 20).unwrap();
+
+    let want = vec![0, 1, 20, 3, 4, 5, 6, 7, 8];
+    let lens: Vec<usize> = primitive.par_chunks().map(|chunk| chunk.len()).collect();
+    assert_eq!(lens, vec![6, 3]);
+    let lens: Vec<usize> = generic.par_chunks().map(|chunk| chunk.len()).collect();
+    assert_eq!(lens, vec![6, 3]);
+    assert_eq!(primitive.par_iter().copied().collect::<Vec<_>>(), want);
+    assert_eq!(generic.par_iter().copied().collect::<Vec<_>>(), want);
+    assert_eq!(union.par_chunks().count(), 2);
+
+    //holes split a run but add nothing themselves
+    primitive.drop_fragment(primitive_id).unwrap();
+    generic.drop_fragment(generic_id).unwrap();
+    primitive.push_vec(vec![9]);
+    generic.push_vec(vec![9]);
+    let lens: Vec<usize> = primitive.par_chunks().map(|chunk| chunk.len()).collect();
+    assert_eq!(lens, vec![6, 1]);
+    let lens: Vec<usize> = generic.par_chunks().map(|chunk| chunk.len()).collect();
+    assert_eq!(lens, vec![6, 1]);
+}
+
+#[test]
+fn par_iter_mut_reaches_values_and_fragments() {
+    let mut primitive = PrimitiveMuted::new((0..5u64).collect(), false);
+    primitive.push_vec(vec![5, 6]);
+    primitive.push_vec(vec![7]);
+    primitive.par_iter_mut().for_each(|val| *val *= 10);
+    assert_eq!(primitive.iter().copied().collect::<Vec<_>>(), (0..8).map(|val| val * 10).collect::<Vec<u64>>());
+
+    let mut small = PrimitiveMuted::new(vec![1i32, 2, 3], true);
+    small.push_vec(vec![4, 5]);
+    small.par_iter_mut().for_each(|val| *val = -*val);
+    assert_eq!(small.iter().copied().collect::<Vec<_>>(), vec![-1, -2, -3, -4, -5]);
+    assert_eq!(small.par_iter().copied().sum::<i32>(), -15);
+}
+
+//read only fragments can't be written so par_iter_mut hands out fewer than len() elements
+#[test]
+fn par_iter_mut_skips_read_only_fragments() {
+    let shared = [2u64, 3, 4];
+    let mut generic = Generic::new(vec![0u64, 1]);
+    generic.push_slice(&shared);
+    generic.push_vec(vec![5]);
+    assert_eq!(generic.len(), 6);
+    assert_eq!(generic.par_iter_mut().count(), 3);
+    generic.par_iter_mut().for_each(|val| *val += 100);
+    assert_eq!(generic.iter().copied().collect::<Vec<_>>(), vec![100, 101, 2, 3, 4, 105]);
+}
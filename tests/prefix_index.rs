@@ -0,0 +1,120 @@
+use muted::{FenwickIndex, LinearIndex, PrefixIndex};
+mod common;
+use common::next;
+
+//every query of index against the same lens summed up the slow way
+fn check<P: PrefixIndex>(index: &P, lens: &[usize]) {
+    assert_eq!(index.len(), lens.len());
+    assert_eq!(index.is_empty(), lens.is_empty());
+    assert_eq!(index.total(), lens.iter().sum::<usize>());
+    let mut start = 0;
+    for (slot, &len) in lens.iter().enumerate() {
+        assert_eq!(index.get(slot), len);
+        assert_eq!(index.prefix(slot), start);
+        for offset in 0..len {
+            assert_eq!(index.find(start + offset), Some((slot, offset)));
+        }
+        start += len;
+    }
+    assert_eq!(index.prefix(lens.len()), start);
+    assert_eq!(index.find(start), None);
+    assert_eq!(index.find(start + 1), None);
+}
+
+//lens with holes (0) mixed in, lengths 1 for inline values and longer fragments
+fn random_len(state: &mut u64) -> usize {
+    match next(state) % 4 {
+        0 => 0,
+        1 => 1,
+        _ => (next(state) % 9) as usize,
+    }
+}
+
+fn random_ops<P: PrefixIndex>(seed: u64) {
+    let mut state = seed;
+    let mut lens: Vec<usize> = Vec::new();
+    let mut index = P::default();
+    for _ in 0..600 {
+        match next(&mut state) % 10 {
+            0..=4 => {
+                let len = random_len(&mut state);
+                index.push(len);
+                lens.push(len);
+            }
+            5..=7 if !lens.is_empty() => {
+                let slot = (next(&mut state) % lens.len() as u64) as usize;
+                let len = random_len(&mut state);
+                index.set(slot, len);
+                lens[slot] = len;
+            }
+            8 if !lens.is_empty() => {
+                let slots = lens.len() - (next(&mut state) % 4) as usize % (lens.len() + 1);
+                index.truncate(slots);
+                lens.truncate(slots);
+            }
+            9 if next(&mut state).is_multiple_of(20) => {
+                index.clear();
+                lens.clear();
+            }
+            _ => (),
+        }
+        check(&index, &lens);
+    }
+    check(&P::from_lens(lens.clone()), &lens);
+}
+
+//the tree has to come out right on either side of every power of two, that is where its nodes change shape
+fn power_of_two_boundaries<P: PrefixIndex>() {
+    for n in [1usize, 2, 3, 4, 5, 7, 8, 9, 15, 16, 17, 31, 32, 33, 64, 65] {
+        let lens: Vec<usize> = (0..n).map(|slot| slot % 3).collect();
+        check(&P::from_lens(lens.clone()), &lens);
+
+        let mut pushed = P::default();
+        for &len in &lens {
+            pushed.push(len);
+        }
+        check(&pushed, &lens);
+
+        //holes at both ends and right on the boundary
+        let mut holes = lens.clone();
+        for slot in [0, n / 2, n - 1] {
+            holes[slot] = 0;
+            pushed.set(slot, 0);
+        }
+        check(&pushed, &holes);
+
+        for slots in [n - 1, n / 2, 0] {
+            let mut cut = P::from_lens(holes.clone());
+            cut.truncate(slots);
+            check(&cut, &holes[..slots]);
+            cut.push(4);
+            let mut grown = holes[..slots].to_vec();
+            grown.push(4);
+            check(&cut, &grown);
+        }
+    }
+}
+
+#[test]
+fn fenwick_matches_naive_prefix_sums() {
+    for seed in [1, 0x9e3779b97f4a7c15, 0xdeadbeef] {
+        random_ops::<FenwickIndex>(seed);
+    }
+}
+
+#[test]
+fn fenwick_power_of_two_boundaries() {
+    power_of_two_boundaries::<FenwickIndex>();
+}
+
+#[test]
+fn linear_matches_naive_prefix_sums() {
+    for seed in [1, 0x9e3779b97f4a7c15, 0xdeadbeef] {
+        random_ops::<LinearIndex>(seed);
+    }
+}
+
+#[test]
+fn linear_power_of_two_boundaries() {
+    power_of_two_boundaries::<LinearIndex>();
+}
@@ -0,0 +1,140 @@
+use muted::{FragmentedVec, PrimitiveMuted, PrimitiveWidth, UnionMuted};
+mod common;
+use common::next;
+
+//the tags of the starting values are filled a byte at a time, lengths that aren't a multiple of 4 leave a tail
+#[test]
+fn starting_values_are_tagged() {
+    let lens: &[u64] = if cfg!(miri) { &[0, 1, 5, 13] } else { &[0, 1, 3, 4, 5, 1001] };
+    for &len in lens {
+        let mut wide = PrimitiveMuted::new((0..len).collect(), false);
+        let mut narrow = PrimitiveMuted::new((0..len as u32).collect(), true);
+        let mut union = UnionMuted::new((0..len).collect());
+        assert_eq!((wide.width(), narrow.width()), (PrimitiveWidth::Bits64, PrimitiveWidth::Bits32));
+        assert!(wide.iter().copied().eq(0..len));
+        assert!(narrow.iter().copied().eq(0..len as u32));
+        assert!(union.iter().copied().eq(0..len));
+        //a value slot is neither a hole nor a fragment
+        for slot in 0..len as usize {
+            assert_eq!(wide.insert_fragment(slot, vec![9]), None);
+            assert_eq!(narrow.take_fragment(slot), None);
+            assert_eq!(union.insert_fragment(slot, vec![9]), None);
+        }
+        let id = wide.push_vec(vec![7, 8]);
+        assert_eq!(wide.fragment_range(id), Some(len as usize..len as usize + 2));
+        narrow.push_vec(vec![7, 8]);
+        union.push_vec_convert(vec![7, 8]);
+        assert_eq!((wide.len(), narrow.len(), union.len()), (len as usize + 2, len as usize + 2, len as usize + 2));
+    }
+}
+
+//64 bit values are adopted as they are, the buffer behind them isn't copied
+//unless i64 is only 4 aligned (32 bit targets), then they are copied into slots that are
+#[test]
+fn wide_values_keep_their_capacity() {
+    let mut values: Vec<i64> = Vec::with_capacity(100);
+    values.extend(-50..0);
+    let mut muted = PrimitiveMuted::new(values, false);
+    let slots = if align_of::<i64>() == 8 { 100 } else { 50 };
+    assert_eq!(muted.stats().slot_bytes, slots * 8);
+    muted.write(49, 1).unwrap();
+    assert_eq!(muted.read(49), Some(&1));
+    assert_eq!(muted.read(0), Some(&-50));
+}
+
+//reads and writes go through the pointer in each slot, checked against plain vecs after every change to the slots
+#[test]
+fn reads_follow_the_slot_pointers() {
+    let mut state = 0x853c49e6748fea9b;
+    let mut muted = PrimitiveMuted::new(vec![0u64, 1, 2], false);
+    //what every slot should hold, None for holes
+    let mut model: Vec<Option<Vec<u64>>> = vec![Some(vec![0]), Some(vec![1]), Some(vec![2])];
+    let mut ids = Vec::new();
+    //miri walks every raw pointer access, a shorter run still covers drop, refill and write
+    let steps = if cfg!(miri) { 60 } else { 400 };
+    for step in 0..steps {
+        match next(&mut state) % 5 {
+            0 | 1 => {
+                let len = next(&mut state) % 6;
+                ids.push(muted.push_vec((step * 100..step * 100 + len).collect()));
+                model.push(Some((step * 100..step * 100 + len).collect()));
+            }
+            2 => if let Some(id) = ids.pop() {
+                if muted.drop_fragment(id).is_some() {
+                    model[id.slot()] = None;
+                }
+            },
+            3 => if let Some(slot) = model.iter().position(Option::is_none) {
+                let id = muted.insert_fragment(slot, vec![step; 3]).unwrap();
+                ids.insert(0, id);
+                model[slot] = Some(vec![step; 3]);
+            },
+            _ => if !muted.is_empty() {
+                let index = (next(&mut state) % muted.len() as u64) as usize;
+                muted.write(index, step).unwrap();
+                let mut left = index;
+                for slot in model.iter_mut().flatten() {
+                    if left < slot.len() {
+                        slot[left] = step;
+                        break;
+                    }
+                    left -= slot.len();
+                }
+            },
+        }
+        let flat: Vec<u64> = model.iter().flatten().flatten().copied().collect();
+        assert_eq!(muted.len(), flat.len());
+        for (index, val) in flat.iter().enumerate() {
+            assert_eq!(muted.read(index), Some(val));
+        }
+        assert!(muted.chunks().flatten().eq(flat.iter()));
+    }
+    let taken = ids.iter().rev().find_map(|id| muted.take_fragment(*id).map(|vec| (id.slot(), vec)));
+    if let Some((slot, vec)) = taken {
+        assert_eq!(Some(vec), model[slot].take());
+    }
+    assert!(muted.iter().eq(model.iter().flatten().flatten()));
+}
+
+//drop_vec takes a slot or an id, insert_vec refills only holes, Drop frees whatever is left
+#[test]
+fn drop_and_insert_vec() {
+    let mut muted = PrimitiveMuted::new(vec![0u32, 1], true);
+    let first = muted.push_vec(vec![2, 3, 4]);
+    let second = muted.push_vec(vec![5, 6]);
+    assert_eq!(muted.len(), 7);
+
+    assert_eq!(muted.drop_vec(first.slot()), Some(()));
+    assert_eq!(muted.drop_vec(first), None);
+    assert_eq!(muted.drop_vec(0usize), None);
+    assert_eq!(muted.len(), 4);
+    assert_eq!(muted.read(2), Some(&5));
+
+    assert_eq!(muted.insert_vec(second.slot(), vec![9]), None);
+    assert_eq!(muted.insert_vec(9, vec![9]), None);
+    let refill = muted.insert_vec(first.slot(), vec![7; 4]).unwrap();
+    assert_eq!(muted.len(), 8);
+    assert_eq!(muted.iter().copied().collect::<Vec<_>>(), vec![0, 1, 7, 7, 7, 7, 5, 6]);
+    assert_eq!(muted.drop_vec(second), Some(()));
+    assert_eq!(muted.fragment(refill), Some(&[7u32; 4][..]));
+    assert_eq!(muted.stats().holes, 1);
+}
+
+//fragment_mut, truncate and shrink_to_fit reach a fragment through the side table, reads through the slot
+//both have to stay usable after the other one was used, which miri checks
+#[test]
+fn side_table_and_slot_pointer_agree() {
+    let mut muted = PrimitiveMuted::new(vec![0u64], false);
+    let id = muted.push_vec(vec![1, 2, 3, 4]);
+    muted.fragment_mut(id).unwrap()[0] = 10;
+    assert_eq!(muted.read(1), Some(&10));
+    muted.write(2, 20).unwrap();
+    assert_eq!(muted.fragment(id), Some(&[10, 20, 3, 4][..]));
+
+    muted.truncate(4);
+    muted.shrink_to_fit();
+    assert_eq!(muted.read(3), Some(&3));
+    assert_eq!(muted.fragment(id), Some(&[10, 20, 3][..]));
+    assert_eq!(muted.stats().fragment_bytes, 3 * 8);
+    assert_eq!(muted.take_fragment(id), Some(vec![10, 20, 3]));
+}
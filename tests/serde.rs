@@ -0,0 +1,97 @@
+#![cfg(feature = "serde")]
+use muted::{FragmentedVec, Muted as Generic, PrimitiveMuted, RawBuf, UnionMuted, WithFragments};
+
+//inline values, a hole, a fragment, an empty fragment and a last fragment, same on every container
+fn fill<M: FragmentedVec<u64>>(mut muted: M) -> M {
+    let dropped = muted.push_vec(vec![3, 4]);
+    muted.push_vec(vec![5, 6, 7]);
+    muted.push_vec(vec![]);
+    muted.push_vec(vec![8]);
+    muted.drop_fragment(dropped).unwrap();
+    muted
+}
+
+//slot by slot what fill() left: values in 0..3, the hole at 3, fragments at 4, 5 (empty) and 6
+fn check_layout<M: FragmentedVec<u64>>(muted: &mut M) {
+    for slot in 0..3 {
+        assert_eq!(muted.take_fragment(slot), None);
+        assert_eq!(muted.insert_fragment(slot, vec![9]), None);
+    }
+    assert_eq!(muted.take_fragment(5), Some(vec![]));
+    assert_eq!(muted.take_fragment(4), Some(vec![5, 6, 7]));
+    assert_eq!(muted.take_fragment(6), Some(vec![8]));
+    for (slot, vec) in [(3, vec![3, 4]), (4, vec![5, 6, 7]), (6, vec![8])] {
+        assert!(muted.insert_fragment(slot, vec).is_some());
+    }
+    assert_eq!(muted.insert_fragment(7, vec![9]), None);
+    assert!(muted.iter().copied().eq(0..9));
+}
+
+#[test]
+fn plain_round_trips_come_back_inline() {
+    let generic = fill(Generic::new(vec![0u64, 1, 2]));
+    let json = serde_json::to_string(&generic).unwrap();
+    assert_eq!(json, "[0,1,2,5,6,7,8]");
+    let back: Generic<u64> = serde_json::from_str(&json).unwrap();
+    assert!(back.iter().eq(generic.iter()));
+    assert_eq!(back.fragment_count(), 0);
+
+    let primitive = fill(PrimitiveMuted::new(vec![0u64, 1, 2], false));
+    assert_eq!(serde_json::to_string(&primitive).unwrap(), json);
+    let back: PrimitiveMuted<u64> = serde_json::from_str(&json).unwrap();
+    assert!(back.iter().eq(primitive.iter()));
+    let narrow: PrimitiveMuted<u32> = serde_json::from_str(&json).unwrap();
+    assert!(narrow.iter().map(|v| *v as u64).eq(primitive.iter().copied()));
+
+    let union = fill(UnionMuted::new(vec![0u64, 1, 2]));
+    assert_eq!(serde_json::to_string(&union).unwrap(), json);
+    let back: UnionMuted<u64> = serde_json::from_str(&json).unwrap();
+    assert!(back.iter().eq(union.iter()));
+
+    let mut buf = RawBuf::new(3);
+    buf.bytes_mut().copy_from_slice(&[0b1001, 0xff, 0]);
+    buf.extend_by(2);
+    let back: RawBuf = serde_json::from_str(&serde_json::to_string(&buf).unwrap()).unwrap();
+    assert_eq!(back, buf);
+}
+
+//values, holes, fragment boundaries and empty fragments all come back where they were
+#[test]
+fn with_fragments_keeps_the_slot_layout() {
+    let json = r#"[{"Val":0},{"Val":1},{"Val":2},"Hole",{"Fragment":[5,6,7]},{"Fragment":[]},{"Fragment":[8]}]"#;
+
+    let generic = fill(Generic::new(vec![0u64, 1, 2]));
+    assert_eq!(serde_json::to_string(&WithFragments(&generic)).unwrap(), json);
+    let mut back: WithFragments<Generic<u64>> = serde_json::from_str(json).unwrap();
+    assert!(back.0.iter().eq(generic.iter()));
+    assert_eq!(serde_json::to_string(&WithFragments(&back.0)).unwrap(), json);
+    check_layout(&mut back.0);
+
+    let primitive = fill(PrimitiveMuted::new(vec![0u64, 1, 2], false));
+    assert_eq!(serde_json::to_string(&WithFragments(&primitive)).unwrap(), json);
+    let mut back: WithFragments<PrimitiveMuted<u64>> = serde_json::from_str(json).unwrap();
+    assert!(back.0.iter().eq(primitive.iter()));
+    assert_eq!(serde_json::to_string(&WithFragments(&back.0)).unwrap(), json);
+    check_layout(&mut back.0);
+    let narrow: WithFragments<PrimitiveMuted<u32>> = serde_json::from_str(json).unwrap();
+    assert_eq!(serde_json::to_string(&WithFragments(&narrow.0)).unwrap(), json);
+
+    let union = fill(UnionMuted::new(vec![0u64, 1, 2]));
+    assert_eq!(serde_json::to_string(&WithFragments(&union)).unwrap(), json);
+    let mut back: WithFragments<UnionMuted<u64>> = serde_json::from_str(json).unwrap();
+    assert!(back.0.iter().eq(union.iter()));
+    assert_eq!(serde_json::to_string(&WithFragments(&back.0)).unwrap(), json);
+    check_layout(&mut back.0);
+}
+
+//PrimitiveMuted only holds i32/u32/i64/u64, anything else is an error instead of a panic
+#[test]
+fn unsupported_primitive_types_are_errors() {
+    let plain = serde_json::from_str::<PrimitiveMuted<u16>>("[1,2]");
+    assert!(plain.err().unwrap().to_string().contains("Unsupported type"));
+    let layout = serde_json::from_str::<WithFragments<PrimitiveMuted<i8>>>(r#"[{"Val":1}]"#);
+    assert!(layout.is_err());
+    //wrong shapes are errors too
+    assert!(serde_json::from_str::<PrimitiveMuted<u64>>(r#"{"a":1}"#).is_err());
+    assert!(serde_json::from_str::<WithFragments<Generic<u64>>>(r#"[{"Frag":[1]}]"#).is_err());
+}
@@ -0,0 +1,81 @@
+use std::thread;
+use muted::{FragmentId, Muted, MutedSnapshot};
+
+type Change = fn(&mut Muted<u64>, &[FragmentId]);
+
+//inline values, three fragments and a hole between them
+fn build(arena: bool) -> (Muted<u64>, Vec<FragmentId>) {
+    let mut muted = Muted::new(vec![0, 1]);
+    muted.set_arena(arena);
+    let ids: Vec<FragmentId> = (1..4).map(|f| muted.push_vec((f * 10..f * 10 + 4).collect())).collect();
+    let dropped = muted.push_vec(vec![40]);
+    muted.push_vec(vec![50, 51]);
+    muted.drop_fragment(dropped).unwrap();
+    (muted, ids)
+}
+
+fn check(snapshot: &MutedSnapshot<u64>, frozen: &[u64]) {
+    assert_eq!(snapshot.len(), frozen.len());
+    assert!(snapshot.iter().eq(frozen.iter()));
+    for (index, val) in frozen.iter().enumerate() {
+        assert_eq!(snapshot.read(index), Some(val));
+    }
+    assert_eq!(snapshot.read(frozen.len()), None);
+}
+
+//whatever the source does afterwards, with or without the arena, the snapshot keeps what it saw
+#[test]
+fn snapshot_ignores_later_changes() {
+    let changes: [(&str, Change); 8] = [
+        ("write", |m, _| m.write(7, 99).unwrap()),
+        ("write inline", |m, _| m.write(0, 99).unwrap()),
+        ("fragment_mut", |m, ids| m.fragment_mut(ids[2]).unwrap().fill(99)),
+        ("push", |m, _| m.push(99)),
+        ("push_vec", |m, _| { m.push_vec(vec![99; 3]); }),
+        ("truncate", |m, _| m.truncate(5)),
+        ("compact", |m, ids| { m.drop_fragment(ids[0]).unwrap(); m.compact(); }),
+        ("insert_vec", |m, _| { m.insert_vec(5, vec![99]).unwrap(); }),
+    ];
+    for arena in [false, true] {
+        for (name, change) in changes {
+            let (mut muted, ids) = build(arena);
+            let frozen: Vec<u64> = muted.iter().copied().collect();
+            let snapshot = muted.snapshot();
+            let again = snapshot.clone();
+            change(&mut muted, &ids);
+            assert!(!muted.iter().eq(frozen.iter()), "{name} changed nothing");
+            check(&snapshot, &frozen);
+            check(&again, &frozen);
+            //the source keeps working after the snapshot is gone
+            drop(snapshot);
+            muted.write(1, 7).unwrap();
+            assert_eq!(muted.read(1), Some(&7), "{name}");
+        }
+    }
+}
+
+//clones of a snapshot are read from other threads while the source keeps changing
+#[test]
+fn snapshot_is_send_and_sync() {
+    fn shareable<S: Send + Sync>(_: &S) {}
+    let (mut muted, _) = build(false);
+    let frozen: Vec<u64> = muted.iter().copied().collect();
+    let snapshot = muted.snapshot();
+    shareable(&snapshot);
+    thread::scope(|scope| {
+        let readers: Vec<_> = (0..4).map(|_| {
+            let snapshot = snapshot.clone();
+            scope.spawn(move || snapshot.iter().copied().collect::<Vec<u64>>())
+        }).collect();
+        let by_ref = scope.spawn(|| snapshot.iter().sum::<u64>());
+        for v in 0..100 {
+            muted.push(v);
+            muted.write(2, v).unwrap();
+        }
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), frozen);
+        }
+        assert_eq!(by_ref.join().unwrap(), frozen.iter().sum::<u64>());
+    });
+    check(&snapshot, &frozen);
+}
@@ -0,0 +1,20 @@
+use std::thread;
+use muted::UnionMuted;
+
+//read only takes shared references, so scoped threads can all read the same UnionMuted
+#[test]
+fn union_reads_from_several_threads() {
+    let mut union = UnionMuted::new((0..10u64).collect());
+    union.push_vec_convert((10..100).collect());
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for index in 0..union.len() {
+                    assert_eq!(union.read(index), Some(&(index as u64)));
+                }
+            });
+        }
+    });
+    union.write(50, 0).unwrap();
+    assert_eq!(union.read(50), Some(&0));
+}
@@ -0,0 +1,70 @@
+use muted::{FragmentedVec, Muted as Generic};
+mod common;
+use common::backends;
+
+//truncate and clear aren't on FragmentedVec, so the same steps are spelled out per container
+//push_vec goes through the trait since UnionMuted::push_vec takes Data
+macro_rules! truncate_over_a_hole {
+    ($muted:expr) => {{
+        let mut muted = $muted;
+        let dropped = FragmentedVec::push_vec(&mut muted, (3..8).collect());
+        FragmentedVec::push_vec(&mut muted, vec![8, 9, 10]);
+        muted.drop_fragment(dropped).unwrap();
+        assert_eq!(muted.len(), 6);
+
+        //len only counts live elements, the hole in front of the cut doesn't shift it
+        muted.truncate(5);
+        assert_eq!(muted.iter().copied().collect::<Vec<u64>>(), vec![0, 1, 2, 8, 9]);
+        assert_eq!(muted.stats().len, 5);
+        assert_eq!(muted.read(4), Some(&9));
+
+        muted.truncate(3);
+        assert_eq!(muted.len(), 3);
+        assert_eq!(muted.iter().count(), 3);
+        assert_eq!(muted.fragment_count(), 0);
+
+        //slots that went away come back as fresh fragments
+        FragmentedVec::push_vec(&mut muted, vec![11, 12]);
+        assert_eq!(muted.iter().copied().collect::<Vec<u64>>(), vec![0, 1, 2, 11, 12]);
+        assert_eq!(muted.stats().len, 5);
+
+        muted.clear();
+        assert!(muted.is_empty());
+        assert_eq!(muted.stats().len, 0);
+        FragmentedVec::push_vec(&mut muted, vec![1]);
+        assert_eq!(muted.read(0), Some(&1));
+    }};
+}
+
+#[test]
+fn truncate_counts_live_elements() {
+    let (generic, primitive, union) = backends(vec![0, 1, 2]);
+    truncate_over_a_hole!(generic);
+    truncate_over_a_hole!(primitive);
+    truncate_over_a_hole!(union);
+}
+
+//pop walks back over holes and empty fragments at the tail and drops them on the way
+#[test]
+fn pop_skips_trailing_holes() {
+    let mut muted = Generic::new(vec![0u64, 1]);
+    muted.push_vec(vec![2, 3]);
+    let dropped = muted.push_vec(vec![4, 5, 6]);
+    muted.push_vec(vec![]);
+    muted.drop_fragment(dropped).unwrap();
+
+    assert_eq!(muted.pop(), Some(3));
+    assert_eq!(muted.fragment_count(), 1);
+    muted.push(7);
+    assert_eq!(muted.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 7]);
+
+    let mut popped = Vec::new();
+    while let Some(val) = muted.pop() {
+        popped.push(val);
+        assert_eq!(muted.len(), muted.iter().count());
+    }
+    assert_eq!(popped, vec![7, 2, 1, 0]);
+    assert_eq!(muted.fragment_count(), 0);
+    muted.push(8);
+    assert_eq!(muted.read(0), Some(&8));
+}